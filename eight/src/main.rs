@@ -1,133 +1,846 @@
-/* A tree is visible if all of the other trees between it and an edge of the grid are shorter than it. */
+/*
+    A tree is visible if all of the other trees between it and an edge of the grid are shorter than it.
+    A tree's scenic score is how far you can see from it in each direction, multiplied together.
+
+    Both questions are answered from one Forest by walking it in straight lines.
+*/
+#![allow(clippy::needless_return)]
 
 use std::fs;
+use std::io;
+use std::iter;
 use std::ops::RangeInclusive;
 use std::str::FromStr;
 
-#[derive(Default, Debug)]
-struct Tree {
-    height: isize,
-    visibility: u8,
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Position {
+    x: isize,
+    y: isize,
 }
 
-impl Tree {
-    fn new(height: isize) -> Tree {
-        let mut tree = Tree::default();
-        tree.height = height;
-        return tree;
+impl Position {
+    fn new(x: isize, y: isize) -> Position {
+        return Position { x, y };
     }
 }
 
-impl FromStr for Tree {
-    type Err = ();
+//shorthand.
+fn p(x: isize, y: isize) -> Position {
+    Position::new(x, y)
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.parse::<isize>() {
-            Ok(height) => Ok(Tree::new(height)),
-            Err(err) => {
-                panic!("Could not parse tree height! {:?} ", err);
-            }
+#[derive(Debug)]
+struct CarteseanWalker {
+    pos: Position,
+    stride: Position,
+    bounds_x: RangeInclusive<isize>,
+    bounds_y: RangeInclusive<isize>,
+}
+
+impl CarteseanWalker {
+    //creates a new walker that has positive bounds inclusive of max
+    // if stride is negative, starts at max
+    fn new(max: Position, stride: Position) -> CarteseanWalker {
+        let mut start = Position::new(0, 0);
+        if stride.x < 0 {
+            start.x = max.x;
+        }
+        if stride.y < 0 {
+            start.y = max.y;
         }
+
+        CarteseanWalker::within(Position::new(0, 0), max, start, stride)
+    }
+
+    //a walker from start inside the inclusive bounds min to max, which may be negative.
+    // any stride works, diagonal or longer than one step.
+    fn within(min: Position, max: Position, start: Position, stride: Position) -> CarteseanWalker {
+        CarteseanWalker {
+            pos: start,
+            stride,
+            bounds_x: RangeInclusive::new(min.x, max.x),
+            bounds_y: RangeInclusive::new(min.y, max.y),
+        }
+    }
+
+    fn contains(&self, pos: &Position) -> bool {
+        self.bounds_x.contains(&pos.x) && self.bounds_y.contains(&pos.y)
     }
 }
 
-fn main() {
-    let input = fs::read_to_string("./8.input").expect("Error while reading");
+impl iter::Iterator for CarteseanWalker {
+    type Item = Position;
 
-    let forest: Vec<Vec<Tree>> = parse_forest(&input);
-    let total = mark_forest(forest);
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.contains(&self.pos) {
+            return None;
+        }
 
-    println!("{:?}", total);
+        let result = Some(self.pos);
+        if self.stride == p(0, 0) {
+            //standing still would go on forever. visit the start once instead.
+            self.bounds_x = RangeInclusive::new(1, 0);
+        }
+        self.pos.x += self.stride.x;
+        self.pos.y += self.stride.y;
+        return result;
+    }
 }
 
-fn parse_forest(s: &str) -> Vec<Vec<Tree>> {
-    s.trim()
-        .split("\n")
-        .map(|row| {
-            row.split_inclusive(|_x| true)
-                .map(|a| {
-                    return a.parse::<Tree>().unwrap();
-                })
-                .collect()
+//one walker for each line through the grid along stride, each starting where its line enters.
+// every position in the grid is on exactly one of the lines.
+fn edge_walkers(bounds: Position, stride: Position) -> impl Iterator<Item = CarteseanWalker> {
+    let grid = CarteseanWalker::within(p(0, 0), bounds, p(0, 0), stride);
+
+    (0..=bounds.y)
+        .flat_map(move |y| (0..=bounds.x).map(move |x| p(x, y)))
+        .filter(move |pos| {
+            stride != p(0, 0) && !grid.contains(&p(pos.x - stride.x, pos.y - stride.y))
         })
-        .collect()
+        .map(move |start| CarteseanWalker::within(p(0, 0), bounds, start, stride))
 }
 
-fn mark_forest(mut forest: Vec<Vec<Tree>>) -> isize {
-    sweep(&mut forest, 1);
-    reverse(&mut forest);
-    sweep(&mut forest, 1);
-    reverse(&mut forest); //not needed but nice to put things back for debugging :D
+//the strides that walk into the forest from the north, east, south and west edges, in that order,
+// so a tree seen along FOUR_WAY[i] gets the same bit as Edge mask i. y grows southwards.
+const FOUR_WAY: [Position; 4] = [
+    Position { x: 0, y: 1 },
+    Position { x: -1, y: 0 },
+    Position { x: 0, y: -1 },
+    Position { x: 1, y: 0 },
+];
 
-    forest = transpose(forest);
+//the same four, then the diagonals.
+const EIGHT_WAY: [Position; 8] = [
+    FOUR_WAY[0],
+    FOUR_WAY[1],
+    FOUR_WAY[2],
+    FOUR_WAY[3],
+    Position { x: -1, y: 1 },
+    Position { x: -1, y: -1 },
+    Position { x: 1, y: -1 },
+    Position { x: 1, y: 1 },
+];
 
-    sweep(&mut forest, 1);
-    reverse(&mut forest);
-    sweep(&mut forest, 1);
-    reverse(&mut forest); //not needed but nice to put things back for debugging :D
+//the four edges of the forest. each gets its own bit in a visibility mask.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edge {
+    North,
+    East,
+    South,
+    West,
+}
 
-    forest = transpose(forest);
+impl Edge {
+    fn mask(self) -> u8 {
+        1 << self as u8
+    }
+}
 
-    let total = forest.iter().fold(0, |acc, x| {
-        acc + x.iter().fold(0, |mut acc, x| {
-            if x.visibility > 0 {
-                acc += 1
-            };
-            acc
-        })
-    });
+impl FromStr for Edge {
+    type Err = String;
 
-    return total;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "north" => Ok(Edge::North),
+            "east" => Ok(Edge::East),
+            "south" => Ok(Edge::South),
+            "west" => Ok(Edge::West),
+            _ => Err(format!("{:?} is not north, east, south or west", s)),
+        }
+    }
 }
 
-fn sweep(forest: &mut Vec<Vec<Tree>>, mask: u8) {
-    for line in forest {
-        let mut hieghest = -1;
-        for mut tree in line {
-            if tree.height > hieghest {
-                tree.visibility += mask;
-                hieghest = tree.height;
-            }
+//tree heights stored row by row, so trees[y][x]. every row is the same length.
+#[derive(Debug)]
+struct Forest {
+    trees: Vec<Vec<isize>>,
+}
+
+impl FromStr for Forest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trees: Vec<Vec<isize>> = s
+            .trim()
+            .lines()
+            .enumerate()
+            .map(|(i, row)| {
+                row.trim_end()
+                    .chars()
+                    .map(|c| {
+                        c.to_digit(10)
+                            .map(|h| h as isize)
+                            .ok_or_else(|| format!("row {}: {:?} is not a tree height", i + 1, c))
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        if trees.is_empty() || trees[0].is_empty() {
+            return Err("there are no trees".to_string());
+        }
+        if let Some(i) = trees.iter().position(|row| row.len() != trees[0].len()) {
+            return Err(format!(
+                "row {} has {} trees but row 1 has {}",
+                i + 1,
+                trees[i].len(),
+                trees[0].len()
+            ));
         }
+
+        return Ok(Forest { trees });
     }
 }
 
-fn reverse(forest: &mut Vec<Vec<Tree>>) {
-    for line in forest {
-        line.reverse();
+impl Forest {
+    //return the inclusive bounds of the forest's indices
+    fn bounds(&self) -> Position {
+        Position::new(
+            (self.trees[0].len() - 1) as isize,
+            (self.trees.len() - 1) as isize,
+        )
+    }
+
+    fn contains(&self, pos: &Position) -> bool {
+        let bounds = self.bounds();
+        (0..=bounds.x).contains(&pos.x) && (0..=bounds.y).contains(&pos.y)
+    }
+
+    fn get(&self, pos: &Position) -> isize {
+        self.trees[pos.y as usize][pos.x as usize]
+    }
+
+    //for every tree, the edges it can be seen from, as the Edge masks or'd together.
+    fn visibility(&self) -> Vec<Vec<u8>> {
+        self.visibility_along(&FOUR_WAY)
+    }
+
+    //bit i is set if the tree can be seen from outside the forest looking along strides[i].
+    // at most eight strides, one per bit.
+    fn visibility_along(&self, strides: &[Position]) -> Vec<Vec<u8>> {
+        assert!(strides.len() <= 8, "only room for eight directions");
+        let mut visibility = vec![vec![0; self.trees[0].len()]; self.trees.len()];
+
+        for (bit, stride) in strides.iter().enumerate() {
+            for walker in edge_walkers(self.bounds(), *stride) {
+                let mut highest = -1;
+                for pos in walker {
+                    if self.get(&pos) > highest {
+                        visibility[pos.y as usize][pos.x as usize] |= 1 << bit;
+                        highest = self.get(&pos);
+                    }
+                }
+            }
+        }
+
+        return visibility;
+    }
+
+    fn count_visible(&self) -> usize {
+        self.trees_where(|mask| mask != 0).len()
+    }
+
+    fn count_visible_along(&self, strides: &[Position]) -> usize {
+        self.visibility_along(strides)
+            .iter()
+            .flatten()
+            .filter(|mask| **mask != 0)
+            .count()
+    }
+
+    //positions of the trees whose visibility mask matches, row by row.
+    fn trees_where(&self, keep: impl Fn(u8) -> bool) -> Vec<Position> {
+        let mut found = Vec::new();
+
+        for (y, row) in self.visibility().iter().enumerate() {
+            for (x, mask) in row.iter().enumerate() {
+                if keep(*mask) {
+                    found.push(p(x as isize, y as isize));
+                }
+            }
+        }
+
+        return found;
+    }
+
+    fn visible_from(&self, edge: Edge) -> Vec<Position> {
+        self.trees_where(|mask| mask & edge.mask() != 0)
+    }
+
+    fn visible_only_from(&self, edge: Edge) -> Vec<Position> {
+        self.trees_where(|mask| mask == edge.mask())
+    }
+
+    //one character per tree: how many edges it can be seen from, or `.` if none.
+    fn render_visibility(&self) -> String {
+        let mut out = String::new();
+
+        for row in self.visibility() {
+            for mask in row {
+                out.push(match mask.count_ones() {
+                    0 => '.',
+                    n => char::from_digit(n, 10).unwrap(),
+                });
+            }
+            out.push('\n');
+        }
+
+        return out;
+    }
+
+    fn score_direction(&self, candidate: &Position, direction: Position) -> isize {
+        let mut walker = CarteseanWalker::new(self.bounds(), direction);
+        walker.pos = *candidate;
+
+        walker.next().unwrap(); //skip starting location.
+
+        let height = self.get(candidate);
+        let mut score = 0;
+
+        for pos in walker {
+            score += 1;
+            if self.get(&pos) >= height {
+                break;
+            }
+        }
+
+        return score;
+    }
+
+    fn score(&self, candidate: &Position) -> isize {
+        self.score_along(candidate, &FOUR_WAY)
+    }
+
+    fn score_along(&self, candidate: &Position, strides: &[Position]) -> isize {
+        return strides
+            .iter()
+            .map(|stride| self.score_direction(candidate, *stride))
+            .product();
+    }
+
+    /*
+        The scenic score of every tree at once, in O(n^2) rather than walking out from each tree.
+
+        Walking a line inwards from an edge, keep a stack of the trees passed so far that nothing
+            since has been as tall as. Before adding a tree, pop everything shorter than it: what is
+            left on top is the tree that blocks its view back towards the edge, or the edge itself.
+        Every tree goes on and comes off each line's stack once.
+    */
+    fn scenic_scores(&self) -> Vec<Vec<isize>> {
+        self.scenic_scores_along(&FOUR_WAY)
+    }
+
+    //each tree looks along every stride, as score_along does, so the lines are walked against it.
+    // distances are counted in trees passed, so a stride of 2 sees half as far as one of 1 in the same space.
+    fn scenic_scores_along(&self, strides: &[Position]) -> Vec<Vec<isize>> {
+        let mut scores = vec![vec![1; self.trees[0].len()]; self.trees.len()];
+
+        for stride in strides {
+            for walker in edge_walkers(self.bounds(), p(-stride.x, -stride.y)) {
+                let mut stack: Vec<(isize, isize)> = Vec::new();
+
+                for (i, pos) in walker.enumerate() {
+                    let height = self.get(&pos);
+                    while stack.last().is_some_and(|(_, h)| *h < height) {
+                        stack.pop();
+                    }
+
+                    let i = i as isize;
+                    let distance = match stack.last() {
+                        Some((blocker, _)) => i - blocker,
+                        None => i,
+                    };
+                    scores[pos.y as usize][pos.x as usize] *= distance;
+
+                    stack.push((i, height));
+                }
+            }
+        }
+
+        return scores;
+    }
+
+    fn best_score(&self) -> isize {
+        self.best_score_along(&FOUR_WAY)
+    }
+
+    fn best_score_along(&self, strides: &[Position]) -> isize {
+        return self
+            .scenic_scores_along(strides)
+            .into_iter()
+            .flatten()
+            .max()
+            .expect("forest should have trees to score");
     }
 }
 
-//from the internet. thank you, internet.
-fn transpose<T>(v: Vec<Vec<T>>) -> Vec<Vec<T>> {
-    assert!(!v.is_empty());
-    let len = v[0].len();
-    let mut iters: Vec<_> = v.into_iter().map(|n| n.into_iter()).collect();
-    (0..len)
-        .map(|_| {
-            iters
-                .iter_mut()
-                .map(|n| n.next().unwrap())
-                .collect::<Vec<T>>()
+/*
+    Pictures of the forest as binary PPM files, one pixel block per tree.
+    PPM is just a short text header and raw RGB bytes, so nothing extra is needed to write it
+        and most image viewers can open it (or convert it to PNG).
+*/
+mod image {
+    use crate::Forest;
+    use std::io::{self, Write};
+
+    type Rgb = [u8; 3];
+
+    //dark blue through green and yellow to white as fraction goes from 0 to 1.
+    pub fn heat(fraction: f64) -> Rgb {
+        const STOPS: [Rgb; 5] = [
+            [10, 10, 60],
+            [20, 120, 140],
+            [60, 180, 60],
+            [240, 220, 40],
+            [255, 255, 255],
+        ];
+
+        let scaled = fraction.clamp(0.0, 1.0) * (STOPS.len() - 1) as f64;
+        let i = (scaled as usize).min(STOPS.len() - 2);
+        let t = scaled - i as f64;
+
+        let mut rgb = [0; 3];
+        for c in 0..3 {
+            let (from, to) = (STOPS[i][c] as f64, STOPS[i + 1][c] as f64);
+            rgb[c] = (from + (to - from) * t).round() as u8;
+        }
+        rgb
+    }
+
+    //values laid out row by row like the forest, each drawn as a scale x scale square.
+    pub fn write_ppm(out: &mut impl Write, pixels: &[Vec<Rgb>], scale: usize) -> io::Result<()> {
+        let width = pixels.first().map_or(0, |row| row.len());
+        writeln!(out, "P6\n{} {}\n255", width * scale, pixels.len() * scale)?;
+
+        for row in pixels {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|rgb| std::iter::repeat_n(*rgb, scale))
+                .flatten()
+                .collect();
+            for _ in 0..scale {
+                out.write_all(&line)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn colour<T: Copy>(values: &[Vec<T>], pick: impl Fn(T) -> Rgb) -> Vec<Vec<Rgb>> {
+        values
+            .iter()
+            .map(|row| row.iter().map(|v| pick(*v)).collect())
+            .collect()
+    }
+
+    //0 to 9 tall.
+    pub fn heights(forest: &Forest) -> Vec<Vec<Rgb>> {
+        colour(&forest.trees, |height| heat(height as f64 / 9.0))
+    }
+
+    //black when hidden, otherwise brighter the more edges the tree can be seen from.
+    pub fn visibility(forest: &Forest) -> Vec<Vec<Rgb>> {
+        colour(&forest.visibility(), |mask| match mask.count_ones() {
+            0 => [0, 0, 0],
+            edges => heat(edges as f64 / 4.0),
+        })
+    }
+
+    //relative to the best score, so the best treehouse spots are the brightest.
+    // a few trees score far higher than the rest, so the square root keeps the others from all looking the same.
+    pub fn scenic_scores(forest: &Forest) -> Vec<Vec<Rgb>> {
+        let best = forest.best_score().max(1) as f64;
+        colour(&forest.scenic_scores(), |score| {
+            heat((score as f64 / best).sqrt())
         })
-        .collect()
+    }
 }
 
-fn test_input(s: &str, expected: isize) {
-    let forest: Vec<Vec<Tree>> = parse_forest(s);
-    let total = mark_forest(forest);
-    assert_eq!(total, expected);
+/*
+    with no arguments, prints the visible tree count and the best scenic score.
+    `map` draws how many edges each tree can be seen from.
+    `images [DIR] [SCALE]` writes height.ppm, visibility.ppm and scenic.ppm into DIR (default `.`),
+        drawing each tree as a SCALE pixel square (default 4).
+    `eight-way` prints the same two answers when trees can also be seen along diagonals.
+    `score X Y` works out one tree's scenic score by looking out from it.
+    `from EDGE` and `only EDGE` list the trees visible from that edge, or from that edge and no other.
+*/
+fn main() {
+    let input = fs::read_to_string("./8.input").expect("Error while reading");
+
+    let forest: Forest = input.parse().unwrap_or_else(|e| panic!("{}", e));
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(mode) = args.first() {
+        let edge = || -> Edge {
+            args.get(1)
+                .expect("which edge? north, east, south or west")
+                .parse()
+                .unwrap_or_else(|e| panic!("{}", e))
+        };
+
+        let trees = match mode.as_str() {
+            "images" => {
+                let dir = std::path::Path::new(args.get(1).map_or(".", |d| d.as_str()));
+                let scale = args
+                    .get(2)
+                    .map(|n| n.parse().expect("SCALE should be a number of pixels"))
+                    .unwrap_or(4);
+
+                for (name, pixels) in [
+                    ("height.ppm", image::heights(&forest)),
+                    ("visibility.ppm", image::visibility(&forest)),
+                    ("scenic.ppm", image::scenic_scores(&forest)),
+                ] {
+                    let path = dir.join(name);
+                    let mut out = io::BufWriter::new(
+                        fs::File::create(&path).expect("could not create image file"),
+                    );
+                    image::write_ppm(&mut out, &pixels, scale).expect("could not write image");
+                    println!("wrote {}", path.display());
+                }
+                return;
+            }
+            "eight-way" => {
+                println!("{:?}", forest.count_visible_along(&EIGHT_WAY));
+                println!("{:?}", forest.best_score_along(&EIGHT_WAY));
+                return;
+            }
+            "map" => {
+                print!("{}", forest.render_visibility());
+                return;
+            }
+            "score" => {
+                let number = |i: usize| -> isize {
+                    args.get(i)
+                        .expect("score needs an x and a y")
+                        .parse()
+                        .expect("x and y should be numbers")
+                };
+                let pos = p(number(1), number(2));
+                if !forest.contains(&pos) {
+                    let bounds = forest.bounds();
+                    panic!(
+                        "{},{} is outside the forest, which runs from 0,0 to {},{}",
+                        pos.x, pos.y, bounds.x, bounds.y
+                    );
+                }
+                println!("{},{} scores {}", pos.x, pos.y, forest.score(&pos));
+                return;
+            }
+            "from" => forest.visible_from(edge()),
+            "only" => forest.visible_only_from(edge()),
+            _ => panic!(
+                "unknown mode {:?}, expected images, eight-way, map, score, from or only",
+                mode
+            ),
+        };
+
+        println!("{} trees", trees.len());
+        for pos in trees {
+            println!("  {},{} height {}", pos.x, pos.y, forest.get(&pos));
+        }
+        return;
+    }
+
+    println!("{:?}", forest.count_visible());
+    println!("{:?}", forest.best_score());
 }
 
-const PROVIDED_INPUT: &str = include_str!("../8.test");
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use proptest::prelude::*;
+
+    const PROVIDED_INPUT: &str = include_str!("../8.test");
+
+    fn forest(s: &str) -> Forest {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(forest("12\n34\n").get(&p(0, 1)), 3);
+        assert_eq!(forest(PROVIDED_INPUT).bounds(), p(4, 4));
+
+        assert!("12\n3\n"
+            .parse::<Forest>()
+            .unwrap_err()
+            .starts_with("row 2"));
+        assert!("12\n3x\n"
+            .parse::<Forest>()
+            .unwrap_err()
+            .starts_with("row 2"));
+        assert!("\n".parse::<Forest>().is_err());
+    }
+
+    #[test]
+    fn test_stride() {
+        let down = CarteseanWalker::new(Position::new(4, 3), Position::new(0, 1));
+
+        let result: Vec<Position> = down.collect();
+
+        let should = vec![p(0, 0), p(0, 1), p(0, 2), p(0, 3)];
+        assert_eq!(result, should);
+
+        let right = CarteseanWalker::new(Position::new(4, 3), Position::new(1, 0));
+        let result: Vec<Position> = right.collect();
+        let should = vec![p(0, 0), p(1, 0), p(2, 0), p(3, 0), p(4, 0)];
+        assert_eq!(result, should);
+
+        let left = CarteseanWalker::new(Position::new(4, 3), Position::new(-1, 0));
+
+        let result: Vec<Position> = left.collect();
+        let should = vec![p(4, 0), p(3, 0), p(2, 0), p(1, 0), p(0, 0)];
+        assert_eq!(result, should);
+    }
+
+    #[test]
+    fn test_any_stride() {
+        let diagonal = CarteseanWalker::within(p(-2, -2), p(2, 2), p(-2, 2), p(1, -1));
+        let result: Vec<Position> = diagonal.collect();
+        assert_eq!(
+            result,
+            vec![p(-2, 2), p(-1, 1), p(0, 0), p(1, -1), p(2, -2)]
+        );
+
+        let long = CarteseanWalker::within(p(0, 0), p(9, 9), p(9, 0), p(-3, 2));
+        let result: Vec<Position> = long.collect();
+        assert_eq!(result, vec![p(9, 0), p(6, 2), p(3, 4), p(0, 6)]);
+
+        let still = CarteseanWalker::within(p(0, 0), p(3, 3), p(1, 1), p(0, 0));
+        assert_eq!(still.collect::<Vec<Position>>(), vec![p(1, 1)]);
+
+        let outside = CarteseanWalker::within(p(0, 0), p(3, 3), p(4, 1), p(-1, 0));
+        assert_eq!(outside.count(), 0);
+    }
+
+    #[test]
+    fn test_edge_walkers_cover_the_grid() {
+        let bounds = p(4, 2);
+        for stride in EIGHT_WAY.iter().chain(&[p(2, 1), p(-1, 3)]) {
+            let mut seen: Vec<Position> = edge_walkers(bounds, *stride).flatten().collect();
+            seen.sort_by_key(|pos| (pos.y, pos.x));
+            seen.dedup();
+            assert_eq!(seen.len(), 15, "stride {:?}", stride);
+            assert_eq!(edge_walkers(bounds, *stride).flatten().count(), 15);
+        }
+    }
+
+    #[test]
+    fn test_input() {
+        let forest = forest(PROVIDED_INPUT);
+
+        let left = CarteseanWalker::new(forest.bounds(), Position::new(-1, 0));
+        let result: Vec<isize> = left.map(|pos| forest.get(&pos)).collect();
+
+        assert_eq!(result, vec![3, 7, 3, 0, 3]);
+    }
+
+    #[test]
+    fn test_visibility() {
+        let all_visible = "123\n456\n789\n\n";
+
+        let short_middle = "222\n212\n222\n";
+
+        assert_eq!(forest(all_visible).count_visible(), 9);
+        assert_eq!(forest(short_middle).count_visible(), 8);
+        assert_eq!(forest(PROVIDED_INPUT).count_visible(), 21);
+    }
+
+    #[test]
+    fn test_visibility_edges() {
+        let forest = forest(PROVIDED_INPUT);
+        let visibility = forest.visibility();
+
+        // the top-left 5 is seen over the 2 to its west and the 0 to its north, but not from the others.
+        assert_eq!(visibility[1][1], Edge::North.mask() | Edge::West.mask());
+        // the middle 3 is hidden.
+        assert_eq!(visibility[2][2], 0);
+        // corners are seen from both of their edges.
+        assert_eq!(visibility[4][4], Edge::South.mask() | Edge::East.mask());
+
+        assert_eq!(forest.visible_from(Edge::North).len(), 10);
+        assert_eq!(
+            forest.visible_only_from(Edge::North),
+            vec![p(1, 0), p(2, 0)]
+        );
+        assert_eq!(
+            forest.visible_only_from(Edge::East),
+            vec![p(4, 1), p(1, 2), p(3, 2), p(4, 2)]
+        );
+        assert_eq!(forest.trees_where(|mask| mask == 0).len(), 4);
+
+        assert_eq!("south".parse::<Edge>(), Ok(Edge::South));
+        assert!("up".parse::<Edge>().is_err());
+    }
+
+    #[test]
+    fn test_render_visibility() {
+        assert_eq!(
+            forest(PROVIDED_INPUT).render_visibility(),
+            "21132\n122.1\n41.11\n1.2.4\n22142\n"
+        );
+    }
+
+    #[test]
+    fn test_heat() {
+        assert_eq!(image::heat(0.0), [10, 10, 60]);
+        assert_eq!(image::heat(1.0), [255, 255, 255]);
+        assert_eq!(image::heat(7.0), [255, 255, 255]);
+        assert_eq!(image::heat(0.5), [60, 180, 60]);
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let forest = forest("09\n90\n90\n");
 
-#[test]
-fn test_scoring() {
-    let all_visible = "123\n456\n789\n\n";
+        let mut out = Vec::new();
+        image::write_ppm(&mut out, &image::heights(&forest), 2).unwrap();
 
-    let short_middle = "222\n212\n222\n";
+        let header = "P6\n4 6\n255\n";
+        assert!(out.starts_with(header.as_bytes()));
+        assert_eq!(out.len(), header.len() + 4 * 6 * 3);
+
+        // the first pixel row is short, short, tall, tall.
+        let pixels = &out[header.len()..];
+        assert_eq!(pixels[0..3], image::heat(0.0));
+        assert_eq!(pixels[3..6], image::heat(0.0));
+        assert_eq!(pixels[6..9], image::heat(1.0));
+        // and each tree is two pixel rows high.
+        assert_eq!(pixels[0..12], pixels[12..24]);
+    }
 
-    test_input(all_visible, 9);
-    test_input(short_middle, 8);
-    test_input(PROVIDED_INPUT, 21);
+    #[test]
+    fn test_images() {
+        let forest = forest(PROVIDED_INPUT);
+
+        let visibility = image::visibility(&forest);
+        assert_eq!(visibility[2][2], [0, 0, 0]);
+        assert_eq!(visibility[0][3], image::heat(0.75));
+
+        // the best spot, 5 at 2,3, is the brightest.
+        let scenic = image::scenic_scores(&forest);
+        assert_eq!(scenic[3][2], image::heat(1.0));
+        assert_eq!(scenic[1][2], image::heat(0.5f64.sqrt()));
+        assert_eq!(scenic[0][0], image::heat(0.0));
+    }
+
+    #[test]
+    fn test_view_score() {
+        let forest = forest(PROVIDED_INPUT);
+
+        let view_score = forest.score(&Position::new(2, 1));
+        assert_eq!(view_score, 4);
+
+        let view_score = forest.score(&Position::new(2, 3));
+        assert_eq!(view_score, 8);
+    }
+
+    #[test]
+    fn test_contains() {
+        let forest = forest(PROVIDED_INPUT);
+        assert!(forest.contains(&p(0, 0)));
+        assert!(forest.contains(&p(4, 4)));
+        for outside in [p(5, 0), p(0, 5), p(-1, 2), p(2, -1)] {
+            assert!(!forest.contains(&outside), "{:?}", outside);
+        }
+    }
+
+    #[test]
+    fn test_score_forest() {
+        assert_eq!(forest(PROVIDED_INPUT).best_score(), 8);
+
+        // one row used to leave no rows at all to score.
+        assert_eq!(forest("12321").best_score(), 0);
+    }
+
+    fn forests() -> impl Strategy<Value = Forest> {
+        //few heights so equal trees blocking each other are common.
+        (1..=12usize, 1..=12usize, 1..=10isize)
+            .prop_flat_map(|(width, height, tallest)| {
+                prop::collection::vec(prop::collection::vec(0..tallest, width), height)
+            })
+            .prop_map(|trees| Forest { trees })
+    }
+
+    fn check_scenic_scores(forest: &Forest) {
+        let knight = [p(2, 1), p(-2, -1), p(1, -2)];
+        for strides in [&FOUR_WAY[..], &EIGHT_WAY[..], &knight[..]] {
+            let scores = forest.scenic_scores_along(strides);
+
+            for (y, row) in scores.iter().enumerate() {
+                for (x, score) in row.iter().enumerate() {
+                    let pos = p(x as isize, y as isize);
+                    assert_eq!(
+                        *score,
+                        forest.score_along(&pos, strides),
+                        "{:?} along {:?} in {:?}",
+                        pos,
+                        strides,
+                        forest
+                    );
+                }
+            }
+        }
+    }
+
+    //looking back from the tree against each stride, everything out to the edge is shorter.
+    fn seen_along(forest: &Forest, pos: Position, stride: Position) -> bool {
+        let back = p(-stride.x, -stride.y);
+        let mut walker = CarteseanWalker::within(p(0, 0), forest.bounds(), pos, back);
+        walker.next();
+        walker.all(|other| forest.get(&other) < forest.get(&pos))
+    }
+
+    fn check_visibility(forest: &Forest) {
+        for (visibility, strides) in [
+            (forest.visibility(), &FOUR_WAY[..]),
+            (forest.visibility_along(&EIGHT_WAY), &EIGHT_WAY[..]),
+        ] {
+            for (y, row) in visibility.iter().enumerate() {
+                for (x, mask) in row.iter().enumerate() {
+                    for (bit, stride) in strides.iter().enumerate() {
+                        let pos = p(x as isize, y as isize);
+                        assert_eq!(
+                            mask & (1 << bit) != 0,
+                            seen_along(forest, pos, *stride),
+                            "{:?} along {:?} in {:?}",
+                            pos,
+                            stride,
+                            forest
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_scenic_scores_match_walking(forest in forests()) {
+            check_scenic_scores(&forest);
+        }
+
+        #[test]
+        fn test_visibility_matches_looking_back(forest in forests()) {
+            check_visibility(&forest);
+        }
+    }
+
+    #[test]
+    fn test_eight_way_visibility() {
+        check_scenic_scores(&forest(PROVIDED_INPUT));
+        check_visibility(&forest(PROVIDED_INPUT));
+
+        // the 4 near the bottom right of the example is hidden along its row and column,
+        // but looks out over the shorter trees on three of its diagonals. only the 5 two steps north west blocks it.
+        let forest = forest(PROVIDED_INPUT);
+        assert_eq!(forest.visibility()[3][3], 0);
+        assert_eq!(
+            forest.visibility_along(&EIGHT_WAY)[3][3],
+            (1 << 4) | (1 << 5) | (1 << 6)
+        );
+        assert!(forest.count_visible_along(&EIGHT_WAY) > forest.count_visible());
+    }
 }
@@ -1,56 +1,867 @@
+#![allow(clippy::needless_return)]
+
+use std::fmt;
 use std::fs;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use std::time;
 
-fn main() {
-    let input = fs::read_to_string("./5.input").expect("Error while reading");
+/*
+    The input is a drawing of the starting stacks, a blank line, then one move per line.
+    Both parts of the puzzle run the same moves over the same drawing, only the crane differs:
+    the CrateMover 9000 moves one crate at a time, the 9001 picks up the whole pile in one go.
+*/
+
+//each column is three characters wide with a space between columns, so column x starts at 4x.
+const COLUMN_WIDTH: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Stacks {
+    //bottom crate first, so the top of each stack is the end of its vec.
+    stacks: Vec<Vec<char>>,
+}
 
-    let mut lines = input.lines();
+impl Stacks {
+    //the crate on top of every stack. empty stacks are skipped.
+    fn tops(&self) -> String {
+        self.stacks.iter().filter_map(|s| s.last()).collect()
+    }
 
-    let mut stacks: Vec<Vec<char>> = Vec::new();
+    //crates in the tallest stack.
+    fn height(&self) -> usize {
+        self.stacks.iter().map(|s| s.len()).max().unwrap_or(0)
+    }
+}
+
+/*
+    Writes the stacks back out in the same format as the puzzle drawing, number row included,
+    so the output can be diffed against the examples or parsed again.
+*/
+impl fmt::Display for Stacks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let height = self.height();
+
+        for level in (0..height).rev() {
+            let row: Vec<String> = self
+                .stacks
+                .iter()
+                .map(|s| match s.get(level) {
+                    Some(c) => format!("[{}]", c),
+                    None => "   ".to_string(),
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+
+        let numbers: Vec<String> = (1..=self.stacks.len())
+            .map(|n| format!("{:^3}", n))
+            .collect();
+        write!(f, "{}", numbers.join(" "))
+    }
+}
 
-    //this will read in the starting thing and consume the line with the column numbers, but we dont need that because math.
-    (&mut lines)
-        .take_while(|line| {
-            return line.trim().starts_with("[");
+impl FromStr for Stacks {
+    type Err = String;
+
+    /* parses the drawing, including the row of column numbers but not the blank line after it.
+    the crate letter for column x sits just inside its bracket, at COLUMN_WIDTH * x + 1. */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rows: Vec<&str> = s.lines().collect();
+
+        let numbers = rows
+            .pop()
+            .ok_or("drawing should have a row of column numbers")?;
+        let num_stacks = numbers.split_whitespace().count();
+
+        //without this check a drawing missing its number row silently loses its bottom row of crates.
+        let counted = numbers
+            .split_whitespace()
+            .zip(1..)
+            .all(|(number, expected)| number.parse() == Ok(expected));
+        if num_stacks == 0 || !counted {
+            return Err(format!(
+                "the last row of the drawing should number the columns from 1, not {:?}",
+                numbers
+            ));
+        }
+
+        let mut stacks = vec![Vec::new(); num_stacks];
+
+        //walk bottom up so we push instead of unshifting.
+        for row in rows.iter().rev() {
+            for (num, c) in row.chars().skip(1).step_by(COLUMN_WIDTH).enumerate() {
+                if !c.is_alphabetic() {
+                    continue; //dont push empty columns
+                }
+
+                stacks
+                    .get_mut(num)
+                    .ok_or(format!(
+                        "crate {} is outside of the {} columns",
+                        c, num_stacks
+                    ))?
+                    .push(c);
+            }
+        }
+
+        return Ok(Stacks { stacks });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Move {
+    count: usize,
+    //zero-based, the input counts stacks from 1.
+    source: usize,
+    dest: usize,
+    //where the move came from in the input, for error messages. 0 if it was not read from a file.
+    line: usize,
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(movement: &str) -> Result<Self, Self::Err> {
+        let mut parts = movement.split(' ');
+        let (Some("move"), Some(count), Some("from"), Some(source), Some("to"), Some(dest), None) = (
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+            parts.next(),
+        ) else {
+            return Err(format!("could not parse movement line {:?}", movement));
+        };
+
+        let number = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| format!("could not parse movement line {:?}", movement))
+        };
+        let stack = |s: &str| {
+            number(s)?
+                .checked_sub(1)
+                .ok_or(format!("stacks are counted from 1 in {:?}", movement))
+        };
+
+        return Ok(Move {
+            count: number(count)?,
+            source: stack(source)?,
+            dest: stack(dest)?,
+            line: 0,
+        });
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "move {} from {} to {}",
+            self.count,
+            self.source + 1,
+            self.dest + 1
+        )
+    }
+}
+
+fn parse_input(s: &str) -> Result<(Stacks, Vec<Move>), String> {
+    let (drawing, moves) = s
+        .split_once("\n\n")
+        .ok_or("drawing and moves should be separated by a blank line")?;
+
+    let stacks = drawing.parse::<Stacks>()?;
+
+    //the moves start after the drawing and the blank line.
+    let first_line = drawing.lines().count() + 2;
+    let moves = moves
+        .lines()
+        .enumerate()
+        .map(|(i, text)| {
+            let mut m = text
+                .parse::<Move>()
+                .map_err(|e| format!("line {}: {}", first_line + i, e))?;
+            m.line = first_line + i;
+            Ok(m)
         })
-        .for_each(|line| {
-            if stacks.len() == 0 {
-                // need to set up our vector.
-                // we have a columnar format where each column width is three and a space between columns
-                // so the line width = 3x columns + x -1 spaces. width = 4x -1. (width + 1) / 4 = x.
-                (0..(line.len() + 1) / 4).for_each(|_| {
-                    stacks.push(Vec::new());
+        .collect::<Result<Vec<Move>, String>>()?;
+
+    return Ok((stacks, moves));
+}
+
+#[derive(Debug, PartialEq)]
+enum MoveError {
+    NoSuchStack {
+        line: usize,
+        stack: usize,
+        num_stacks: usize,
+    },
+    NotEnoughCrates {
+        line: usize,
+        stack: usize,
+        wanted: usize,
+        available: usize,
+    },
+}
+
+//stack numbers are shown counted from 1, like the input.
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::NoSuchStack {
+                line,
+                stack,
+                num_stacks,
+            } => write!(
+                f,
+                "line {}: there is no stack {}, only {} stacks",
+                line,
+                stack + 1,
+                num_stacks
+            ),
+            MoveError::NotEnoughCrates {
+                line,
+                stack,
+                wanted,
+                available,
+            } => write!(
+                f,
+                "line {}: can not move {} crates from stack {}, it only has {}",
+                line,
+                wanted,
+                stack + 1,
+                available
+            ),
+        }
+    }
+}
+
+impl Stacks {
+    //check a move can be made before touching anything, so a failed move leaves the stacks as they were.
+    fn validate(&self, m: &Move) -> Result<(), MoveError> {
+        for stack in [m.source, m.dest] {
+            if stack >= self.stacks.len() {
+                return Err(MoveError::NoSuchStack {
+                    line: m.line,
+                    stack,
+                    num_stacks: self.stacks.len(),
                 });
+            }
+        }
+
+        let available = self.stacks[m.source].len();
+        if available < m.count {
+            return Err(MoveError::NotEnoughCrates {
+                line: m.line,
+                stack: m.source,
+                wanted: m.count,
+                available,
+            });
+        }
+
+        return Ok(());
+    }
+
+    fn lift(&mut self, from: usize, to: usize, count: usize) {
+        let source = &mut self.stacks[from];
+        let split_pos = source.len() - count;
+        let mut tmp = source.split_off(split_pos);
+        self.stacks[to].append(&mut tmp);
+    }
+}
+
+/*
+    A crane picks up as many crates as it can from the top of the source stack and sets them
+    on the destination without changing their order, repeating until `count` crates have moved.
+*/
+trait Crane {
+    //how many crates can be lifted at once. a crane that can not lift anything would never finish a move.
+    fn capacity(&self) -> NonZeroUsize;
+
+    //the size of each lift, in the order they happen.
+    fn lifts(&self, m: &Move) -> Vec<usize> {
+        let capacity = self.capacity().get();
+        let mut lifts = vec![capacity; m.count / capacity];
+        let rest = m.count % capacity;
+        if rest > 0 {
+            lifts.push(rest);
+        }
+        return lifts;
+    }
+
+    fn execute(&self, stacks: &mut Stacks, m: &Move) -> Result<(), MoveError> {
+        stacks.validate(m)?;
+
+        for lifted in self.lifts(m) {
+            stacks.lift(m.source, m.dest, lifted);
+        }
+
+        return Ok(());
+    }
+
+    //put the crates back where they came from. the last lift made is on top, so it goes back first.
+    fn undo(&self, stacks: &mut Stacks, m: &Move) {
+        for lifted in self.lifts(m).into_iter().rev() {
+            stacks.lift(m.dest, m.source, lifted);
+        }
+    }
+
+    fn run(&self, stacks: &mut Stacks, moves: &[Move]) -> Result<(), MoveError> {
+        for m in moves {
+            self.execute(stacks, m)?;
+        }
+        return Ok(());
+    }
+}
+
+//moves one crate at a time, so a pile ends up reversed.
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn capacity(&self) -> NonZeroUsize {
+        NonZeroUsize::MIN
+    }
+}
+
+//moves the whole pile at once, so the order is preserved.
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn capacity(&self) -> NonZeroUsize {
+        NonZeroUsize::MAX
+    }
+}
+
+//moves up to `capacity` crates at a time.
+struct LimitedCrane {
+    capacity: NonZeroUsize,
+}
+
+impl LimitedCrane {
+    //None for a capacity of 0.
+    fn new(capacity: usize) -> Option<LimitedCrane> {
+        NonZeroUsize::new(capacity).map(|capacity| LimitedCrane { capacity })
+    }
+}
+
+impl Crane for LimitedCrane {
+    fn capacity(&self) -> NonZeroUsize {
+        self.capacity
+    }
+}
+
+/*
+    Steps a crane through the moves one at a time, remembering how many have been made
+    so they can be undone again, or so we can jump straight to the stacks as of move K.
+*/
+struct MoveLog<'a, C: Crane> {
+    crane: &'a C,
+    moves: &'a [Move],
+    stacks: Stacks,
+    done: usize,
+}
+
+impl<'a, C: Crane> MoveLog<'a, C> {
+    fn new(crane: &'a C, stacks: &Stacks, moves: &'a [Move]) -> MoveLog<'a, C> {
+        MoveLog {
+            crane,
+            moves,
+            stacks: stacks.clone(),
+            done: 0,
+        }
+    }
+
+    //make the next move. returns None once every move has been made.
+    fn step(&mut self) -> Result<Option<&Move>, MoveError> {
+        let Some(m) = self.moves.get(self.done) else {
+            return Ok(None);
+        };
+
+        self.crane.execute(&mut self.stacks, m)?;
+        self.done += 1;
+        return Ok(Some(m));
+    }
+
+    //take back the last move. returns None when back at the start.
+    fn undo(&mut self) -> Option<&Move> {
+        if self.done == 0 {
+            return None;
+        }
+
+        self.done -= 1;
+        let m = &self.moves[self.done];
+        self.crane.undo(&mut self.stacks, m);
+        return Some(m);
+    }
+
+    //step forwards or backwards until exactly `k` moves have been made.
+    fn seek(&mut self, k: usize) -> Result<&Stacks, MoveError> {
+        let k = k.min(self.moves.len());
+
+        while self.done > k {
+            self.undo();
+        }
+        while self.done < k {
+            self.step()?;
+        }
+
+        return Ok(&self.stacks);
+    }
+}
+
+fn run_to_end(crane: &impl Crane, stacks: &Stacks, moves: &[Move]) -> Result<Stacks, MoveError> {
+    let mut stacks = stacks.clone();
+    crane.run(&mut stacks, moves)?;
+    return Ok(stacks);
+}
+
+fn print_run(label: &str, crane: &impl Crane, stacks: &Stacks, moves: &[Move]) {
+    match run_to_end(crane, stacks, moves) {
+        Ok(end) => println!("{}: {}\n{}\n", label, end.tops(), end),
+        Err(e) => println!("{}: {}\n", label, e),
+    }
+}
+
+/*
+    with no arguments, runs both CrateMovers over the whole input.
+    pass a number to also run a crane that lifts that many crates at a time,
+    or `at K` to see the stacks for both CrateMovers as of move K.
+    `animate [9000|9001] [millis]` plays the moves back in the terminal, one step per keypress
+        unless a delay between moves is given.
+*/
+fn main() {
+    let input = fs::read_to_string("./5.input").expect("Error while reading");
+
+    let (stacks, moves) = parse_input(&input).unwrap_or_else(|e| panic!("{}", e));
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if let Some("animate") = args.first().map(|a| a.as_str()) {
+        let millis = args
+            .get(2)
+            .map(|m| m.parse::<u64>().expect("speed should be in milliseconds"));
+        let delay = millis.map(time::Duration::from_millis);
+
+        match args.get(1).map(|c| c.as_str()) {
+            Some("9000") => playback::play(&CrateMover9000, &stacks, &moves, delay),
+            Some("9001") | None => playback::play(&CrateMover9001, &stacks, &moves, delay),
+            Some(crane) => panic!("unknown crane {:?}, expected 9000 or 9001", crane),
+        }
+        .expect("could not draw to the terminal");
+        return;
+    }
+
+    if let [mode, k] = args.as_slice() {
+        assert_eq!(mode, "at", "unknown mode {:?}", mode);
+        let k = k.parse::<usize>().expect("K should be a move number");
+
+        let mut log = MoveLog::new(&CrateMover9000, &stacks, &moves);
+        match log.seek(k) {
+            Ok(at) => println!("CrateMover 9000 after {} moves:\n{}\n", k, at),
+            Err(e) => println!("CrateMover 9000: {}\n", e),
+        }
+
+        let mut log = MoveLog::new(&CrateMover9001, &stacks, &moves);
+        match log.seek(k) {
+            Ok(at) => println!("CrateMover 9001 after {} moves:\n{}\n", k, at),
+            Err(e) => println!("CrateMover 9001: {}\n", e),
+        }
+        return;
+    }
+
+    print_run("CrateMover 9000", &CrateMover9000, &stacks, &moves);
+    print_run("CrateMover 9001", &CrateMover9001, &stacks, &moves);
+
+    if let [capacity] = args.as_slice() {
+        let crane = capacity
+            .parse::<usize>()
+            .ok()
+            .and_then(LimitedCrane::new)
+            .expect("capacity should be a positive number");
+
+        print_run(
+            &format!("Crane lifting up to {}", capacity),
+            &crane,
+            &stacks,
+            &moves,
+        );
+    }
+}
+
+/*
+    Terminal playback of a MoveLog. The stacks are drawn in the same columns as the puzzle drawing,
+    with the crates moved by the latest move shown in red.
+
+    keys: space plays/pauses, n or right steps forward, b or left steps back,
+          + and - change the speed, q quits.
+*/
+mod playback {
+    use crate::{Crane, Move, MoveLog, Stacks, COLUMN_WIDTH};
+    use crossterm::{
+        cursor,
+        event::{self, Event, KeyCode},
+        execute, queue,
+        style::{self, Stylize},
+        terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    };
+    use std::io::{self, stdout, Write};
+    use std::time::Duration;
+
+    //rows above the stacks used for the status lines.
+    const HEADER_ROWS: u16 = 3;
+
+    //where each crate is drawn: (x, y counted from the top of the stacks, crate, moved by `last`)
+    pub fn layout(stacks: &Stacks, last: Option<&Move>) -> Vec<(u16, u16, char, bool)> {
+        let height = stacks.height();
+        let mut cells = Vec::new();
+
+        for (num, stack) in stacks.stacks.iter().enumerate() {
+            //the crates a move put down are the top `count` of its destination.
+            let in_flight_from = match last {
+                Some(m) if m.dest == num => stack.len().saturating_sub(m.count),
+                _ => stack.len(),
             };
 
-            let mut num = 0;
-            let mut row = line.chars().into_iter();
-            row.next(); //skip opening paren.
+            for (level, c) in stack.iter().enumerate() {
+                cells.push((
+                    (num * COLUMN_WIDTH) as u16,
+                    (height - 1 - level) as u16,
+                    *c,
+                    level >= in_flight_from,
+                ));
+            }
+        }
+
+        return cells;
+    }
+
+    fn draw<C: Crane>(log: &MoveLog<C>, status: &str) -> io::Result<()> {
+        let mut stdout = stdout();
+        let last = log.done.checked_sub(1).map(|i| &log.moves[i]);
+
+        queue!(
+            stdout,
+            terminal::Clear(terminal::ClearType::All),
+            cursor::MoveTo(0, 0),
+            style::Print(format!("move {}/{}", log.done, log.moves.len())),
+        )?;
+
+        if let Some(m) = last {
+            queue!(stdout, style::Print(format!(": {} (line {})", m, m.line)))?;
+        }
+
+        queue!(
+            stdout,
+            cursor::MoveTo(0, 1),
+            style::PrintStyledContent(status.to_string().dark_grey())
+        )?;
+
+        let height = log.stacks.height() as u16;
+
+        for (x, y, c, in_flight) in layout(&log.stacks, last) {
+            let text = format!("[{}]", c);
+            queue!(
+                stdout,
+                cursor::MoveTo(x, HEADER_ROWS + y),
+                style::PrintStyledContent(if in_flight { text.red() } else { text.white() })
+            )?;
+        }
+
+        for num in 0..log.stacks.stacks.len() {
+            queue!(
+                stdout,
+                cursor::MoveTo((num * COLUMN_WIDTH) as u16, HEADER_ROWS + height),
+                style::Print(format!("{:^3}", num + 1))
+            )?;
+        }
+
+        stdout.flush()
+    }
+
+    //steps on a timer when `delay` is given, otherwise only on keypresses.
+    pub fn play<C: Crane>(
+        crane: &C,
+        stacks: &Stacks,
+        moves: &[Move],
+        delay: Option<Duration>,
+    ) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen, cursor::Hide)?;
+
+        let result = run(&mut MoveLog::new(crane, stacks, moves), delay);
 
-            row.step_by(4).for_each(|c| {
-                if c.is_alphabetic() {
-                    stacks[num].push(c);
+        execute!(stdout(), cursor::Show, LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()?;
+
+        result
+    }
+
+    fn run<C: Crane>(log: &mut MoveLog<C>, delay: Option<Duration>) -> io::Result<()> {
+        let mut speed = delay.unwrap_or(Duration::from_millis(250));
+        let mut playing = delay.is_some();
+        let mut error = None;
+
+        loop {
+            let status = match &error {
+                Some(e) => format!("{}", e),
+                None => format!(
+                    "{} every {}ms. space: play/pause, n/b: step, +/-: speed, q: quit",
+                    if playing { "playing" } else { "paused" },
+                    speed.as_millis()
+                ),
+            };
+            draw(log, &status)?;
+
+            //while playing, no keypress before the timeout means it is time for the next move.
+            let key = if !playing || event::poll(speed)? {
+                match event::read()? {
+                    Event::Key(key) => Some(key.code),
+                    _ => continue,
+                }
+            } else {
+                None
+            };
+
+            let forward = match key {
+                None | Some(KeyCode::Char('n')) | Some(KeyCode::Right) => true,
+                Some(KeyCode::Char('b')) | Some(KeyCode::Left) => {
+                    log.undo();
+                    error = None;
+                    false
+                }
+                Some(KeyCode::Char(' ')) => {
+                    playing = !playing;
+                    false
+                }
+                Some(KeyCode::Char('+')) => {
+                    speed = (speed / 2).max(Duration::from_millis(1));
+                    false
+                }
+                Some(KeyCode::Char('-')) => {
+                    speed *= 2;
+                    false
                 }
-                num += 1;
+                Some(KeyCode::Char('q')) | Some(KeyCode::Esc) => return Ok(()),
+                Some(_) => false,
+            };
+
+            if forward {
+                //stop at the end, or at a move that can not be made.
+                match log.step() {
+                    Ok(Some(_)) => {}
+                    Ok(None) => playing = false,
+                    Err(e) => {
+                        error = Some(e);
+                        playing = false;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const PROVIDED_INPUT: &str = include_str!("../5.test");
+
+    fn tops(crane: &impl Crane, stacks: &Stacks, moves: &[Move]) -> String {
+        run_to_end(crane, stacks, moves).unwrap().tops()
+    }
+
+    #[test]
+    fn test_parse_drawing() {
+        let (stacks, moves) = parse_input(PROVIDED_INPUT).unwrap();
+
+        assert_eq!(
+            stacks.stacks,
+            vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']]
+        );
+        assert_eq!(moves.len(), 4);
+        assert_eq!(
+            moves[1],
+            Move {
+                count: 3,
+                source: 0,
+                dest: 2,
+                line: 7
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_input(" 1 \n\nmove 1 from 1 to 1\nmove one from 1 to 1\n"),
+            Err("line 4: could not parse movement line \"move one from 1 to 1\"".to_string())
+        );
+        assert_eq!(
+            "[A]\n[B]".parse::<Stacks>(),
+            Err(
+                "the last row of the drawing should number the columns from 1, not \"[B]\""
+                    .to_string()
+            )
+        );
+        assert!(" 1   3 ".parse::<Stacks>().is_err());
+        assert!("".parse::<Stacks>().is_err());
+        assert_eq!(
+            "[A]\n 1 ".parse::<Stacks>(),
+            Ok(Stacks {
+                stacks: vec![vec!['A']]
             })
-        });
+        );
+        assert_eq!(
+            "move 1 from 0 to 1".parse::<Move>(),
+            Err("stacks are counted from 1 in \"move 1 from 0 to 1\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_cranes() {
+        let (stacks, moves) = parse_input(PROVIDED_INPUT).unwrap();
+
+        assert_eq!(tops(&CrateMover9000, &stacks, &moves), "CMZ");
+        assert_eq!(tops(&CrateMover9001, &stacks, &moves), "MCD");
 
-    stacks.iter_mut().for_each(|a| a.reverse());
+        assert_eq!(tops(&LimitedCrane::new(1).unwrap(), &stacks, &moves), "CMZ");
+        assert_eq!(tops(&LimitedCrane::new(3).unwrap(), &stacks, &moves), "MCD");
+    }
 
-    lines.next();
+    #[test]
+    fn test_crane_needs_capacity() {
+        assert!(LimitedCrane::new(0).is_none());
+        assert_eq!(LimitedCrane::new(4).unwrap().capacity().get(), 4);
+    }
 
-    lines.map(|movement| {
-        let mut parts = movement.split(" ");
-        let (Some("move"), Some(count), Some("from"), Some(source), Some("to"), Some(dest), None) = (parts.next(),parts.next(),parts.next(),parts.next(),parts.next(),parts.next(),parts.next()) else{
-            panic!("could not parse movement line {:?}", movement);
+    #[test]
+    fn test_limited_crane_moves_in_chunks() {
+        let mut stacks = Stacks {
+            stacks: vec![vec!['A', 'B', 'C', 'D', 'E'], vec![]],
         };
+        let m = Move {
+            count: 5,
+            source: 0,
+            dest: 1,
+            line: 0,
+        };
+
+        let crane = LimitedCrane::new(2).unwrap();
+        crane.execute(&mut stacks, &m).unwrap();
+        assert_eq!(stacks.stacks[1], vec!['D', 'E', 'B', 'C', 'A']);
+
+        crane.undo(&mut stacks, &m);
+        assert_eq!(stacks.stacks[0], vec!['A', 'B', 'C', 'D', 'E']);
+        assert!(stacks.stacks[1].is_empty());
+    }
+
+    #[test]
+    fn test_invalid_moves() {
+        let (stacks, _) = parse_input(PROVIDED_INPUT).unwrap();
+        let moves = parse_input(" 1   2   3 \n\nmove 1 from 3 to 4\n")
+            .unwrap()
+            .1;
+
+        let mut after = stacks.clone();
+        assert_eq!(
+            CrateMover9000.run(&mut after, &moves),
+            Err(MoveError::NoSuchStack {
+                line: 3,
+                stack: 3,
+                num_stacks: 3
+            })
+        );
+        assert_eq!(after, stacks);
+
+        let moves = parse_input(" 1 \n\nmove 2 from 3 to 1\n").unwrap().1;
+        let error = CrateMover9001.run(&mut after, &moves).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 3: can not move 2 crates from stack 3, it only has 1"
+        );
+        assert_eq!(after, stacks);
+    }
+
+    #[test]
+    fn test_move_log() {
+        let (stacks, moves) = parse_input(PROVIDED_INPUT).unwrap();
 
-        return (count.parse::<usize>().unwrap(), source.parse::<usize>().unwrap()-1, dest.parse::<usize>().unwrap()-1);
-    }).for_each(|(count, source, dest)| {
-        for _ in 0..count {
-            let item = stacks[source].pop().unwrap();
-            stacks[dest].push(item);
+        for crane in [
+            &LimitedCrane::new(1).unwrap(),
+            &LimitedCrane::new(2).unwrap(),
+            &LimitedCrane::new(100).unwrap(),
+        ] {
+            let mut log = MoveLog::new(crane, &stacks, &moves);
+
+            while log.step().unwrap().is_some() {}
+            assert_eq!(log.step(), Ok(None));
+            assert_eq!(log.stacks, run_to_end(crane, &stacks, &moves).unwrap());
+
+            for k in (0..moves.len()).rev() {
+                assert_eq!(log.undo(), Some(&moves[k]));
+                assert_eq!(log.stacks, run_to_end(crane, &stacks, &moves[..k]).unwrap());
+            }
+            assert_eq!(log.undo(), None);
+
+            for k in [3, 1, 4, 0, 2] {
+                let expected = run_to_end(crane, &stacks, &moves[..k]).unwrap();
+                assert_eq!(log.seek(k), Ok(&expected));
+            }
         }
-    });
+    }
+
+    #[test]
+    fn test_display_matches_drawing() {
+        let (stacks, _) = parse_input(PROVIDED_INPUT).unwrap();
+        let (drawing, _) = PROVIDED_INPUT.split_once("\n\n").unwrap();
+
+        assert_eq!(stacks.to_string(), drawing);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let (stacks, moves) = parse_input(PROVIDED_INPUT).unwrap();
+
+        for i in 0..=moves.len() {
+            let state = run_to_end(&CrateMover9000, &stacks, &moves[..i]).unwrap();
+            assert_eq!(state.to_string().parse::<Stacks>(), Ok(state));
+        }
+
+        let end = run_to_end(&CrateMover9001, &stacks, &moves).unwrap();
+        assert_eq!(
+            end.to_string(),
+            "        [D]
+        [N]
+        [Z]
+[M] [C] [P]
+ 1   2   3 "
+        );
+    }
+
+    #[test]
+    fn test_playback_layout() {
+        let (stacks, moves) = parse_input(PROVIDED_INPUT).unwrap();
+        let mut log = MoveLog::new(&CrateMover9001, &stacks, &moves);
+
+        assert_eq!(
+            playback::layout(&log.stacks, None),
+            vec![
+                (0, 2, 'Z', false),
+                (0, 1, 'N', false),
+                (4, 2, 'M', false),
+                (4, 1, 'C', false),
+                (4, 0, 'D', false),
+                (8, 2, 'P', false),
+            ]
+        );
 
-    dbg!(stacks);
+        log.seek(2).unwrap();
+        let in_flight: Vec<char> = playback::layout(&log.stacks, Some(&moves[1]))
+            .into_iter()
+            .filter(|(_, _, _, in_flight)| *in_flight)
+            .map(|(_, _, c, _)| c)
+            .collect();
+        assert_eq!(in_flight, vec!['Z', 'N', 'D']);
+    }
 }
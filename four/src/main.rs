@@ -1,66 +1,433 @@
-//use itertools::Itertools;
+#![allow(clippy::needless_return)]
+
+use std::fmt;
 use std::fs;
+use std::str::FromStr;
 
-//Ah, i should have added these as extension methods to the built-in RangeInclusive
 // neat technique https://fasterthanli.me/series/advent-of-code-2022/part-4
 
-//returns true IFF "me" fully contains "you", but not the opposite
-fn fully_contains(me: &Vec<i32>, you: &Vec<i32>) -> bool {
-    return me[0] <= you[0] && me[1] >= you[1];
+//the inclusive range of section ids assigned to one elf. start <= end always holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Assignment {
+    start: i32,
+    end: i32,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum AssignmentError {
+    Malformed(String),
+    Reversed { start: i32, end: i32 },
+}
+
+impl fmt::Display for AssignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssignmentError::Malformed(s) => write!(f, "could not parse assignment {:?}", s),
+            AssignmentError::Reversed { start, end } => {
+                write!(f, "assignment {}-{} ends before it starts", start, end)
+            }
+        }
+    }
 }
 
-//returns true if the two 2-ary vecs overlap at all. no need to check the flip.
-fn overlaps_at_all(me: &Vec<i32>, you: &Vec<i32>) -> bool {
-    /* things overlap if either the start or end is contained in the other range. */
+impl Assignment {
+    fn new(start: i32, end: i32) -> Result<Assignment, AssignmentError> {
+        if start > end {
+            return Err(AssignmentError::Reversed { start, end });
+        }
+        return Ok(Assignment { start, end });
+    }
+
+    //number of sections covered. done in i64 so a range reaching i32::MAX can not overflow.
+    fn len(&self) -> usize {
+        (self.end as i64 - self.start as i64 + 1) as usize
+    }
+
+    //returns true IFF self fully contains other, but not the opposite
+    fn contains(&self, other: &Assignment) -> bool {
+        return self.start <= other.start && self.end >= other.end;
+    }
+
+    //returns true if the two overlap at all. no need to check the flip.
+    fn overlaps(&self, other: &Assignment) -> bool {
+        return self.start <= other.end && other.start <= self.end;
+    }
+
+    fn intersection(&self, other: &Assignment) -> Option<Assignment> {
+        if !self.overlaps(other) {
+            return None;
+        }
+
+        return Some(Assignment {
+            start: self.start.max(other.start),
+            end: self.end.min(other.end),
+        });
+    }
 
-    //i do NOT love the manual +1 because of range's upper-bound exclusivity :(
-    let me = std::ops::RangeInclusive::new(me[0], me[1]);
-    let you = std::ops::RangeInclusive::new(you[0], you[1]);
+    //the single range covering both, if they overlap or touch. disjoint ranges have no union range.
+    fn union(&self, other: &Assignment) -> Option<Assignment> {
+        if self.start as i64 > other.end as i64 + 1 || other.start as i64 > self.end as i64 + 1 {
+            return None;
+        }
 
-    return me.contains(&you.start())
-        || me.contains(&you.end())
-        || you.contains(&me.start())
-        || you.contains(&me.end());
+        return Some(Assignment {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        });
+    }
 }
 
-fn main() {
-    let input = fs::read_to_string("./4.input").expect("Error while reading");
+impl fmt::Display for Assignment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl FromStr for Assignment {
+    type Err = AssignmentError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let malformed = || AssignmentError::Malformed(s.to_string());
+
+        let (start, end) = s.split_once('-').ok_or_else(malformed)?;
+        let start = start.trim().parse::<i32>().map_err(|_| malformed())?;
+        let end = end.trim().parse::<i32>().map_err(|_| malformed())?;
+
+        Assignment::new(start, end)
+    }
+}
+
+fn parse_pair(line: &str) -> Result<(Assignment, Assignment), AssignmentError> {
+    let (me, you) = line
+        .split_once(',')
+        .ok_or_else(|| AssignmentError::Malformed(line.to_string()))?;
+
+    return Ok((me.parse()?, you.parse()?));
+}
 
-    let total = input
+fn parse_input(input: &str) -> Result<Vec<(Assignment, Assignment)>, String> {
+    input
         .lines()
-        .map(|pair| {
-            pair.split(",")
-                .map(|elf| {
-                    elf.split("-")
-                        .map(|s| s.parse::<i32>().unwrap())
-                        .collect::<Vec<i32>>() //should collect_tuple into a pair here, maybe map to range at this point.
-                })
-                .collect::<Vec<Vec<i32>>>() //again, collect into a tuple or a type
-        })
-        .map(|elves| {
-            let fully_overlapped =
-                if fully_contains(&elves[0], &elves[1]) || fully_contains(&elves[1], &elves[0]) {
-                    1
-                } else {
-                    0
-                };
+        .enumerate()
+        .map(|(i, line)| parse_pair(line).map_err(|e| format!("line {}: {}", i + 1, e)))
+        .collect()
+}
+
+//returns (pairs where one fully contains the other, pairs that overlap at all)
+fn count_overlaps(pairs: &[(Assignment, Assignment)]) -> (usize, usize) {
+    let fully_overlapped = pairs
+        .iter()
+        .filter(|(me, you)| me.contains(you) || you.contains(me))
+        .count();
+
+    let partial_overlap = pairs
+        .iter()
+        .filter(|(me, you)| me.intersection(you).is_some())
+        .count();
+
+    return (fully_overlapped, partial_overlap);
+}
+
+/*
+    Coverage looks at every elf in the file at once instead of pair by pair.
+    Elves are identified by (line, position in pair), both counted from 1.
+
+    Sections are not counted one by one, since a single elf can cover billions of them.
+    Instead every assignment start and end is sorted and swept once, splitting the span from the
+        lowest to the highest section into segments where the number of elves does not change.
+*/
+type ElfId = (usize, usize);
+
+struct Coverage {
+    elves: Vec<(ElfId, Assignment)>,
+    //back to back from the lowest assigned section to the highest, each with how many elves cover it.
+    segments: Vec<(Assignment, usize)>,
+}
+
+impl Coverage {
+    fn new(pairs: &[(Assignment, Assignment)]) -> Coverage {
+        let elves: Vec<(ElfId, Assignment)> = pairs
+            .iter()
+            .enumerate()
+            .flat_map(|(i, (me, you))| [((i + 1, 1), *me), ((i + 1, 2), *you)])
+            .collect();
 
-            let partial_overlap = if overlaps_at_all(&elves[0], &elves[1]) {
-                println!("{:?} {:?}", elves[0], elves[1]);
-                1
-            } else {
-                0
+        //the count goes up at a start and down just after an end. i64 so i32::MAX + 1 fits.
+        let mut events: Vec<(i64, i64)> = elves
+            .iter()
+            .flat_map(|(_, a)| [(a.start as i64, 1), (a.end as i64 + 1, -1)])
+            .collect();
+        events.sort_unstable();
+
+        let mut segments = Vec::new();
+        let mut count = 0;
+        for (i, (section, change)) in events.iter().enumerate() {
+            count += change;
+
+            let Some((next, _)) = events.get(i + 1) else {
+                break;
             };
+            if next > section {
+                segments.push((
+                    Assignment {
+                        start: *section as i32,
+                        end: (next - 1) as i32,
+                    },
+                    count as usize,
+                ));
+            }
+        }
+
+        return Coverage { elves, segments };
+    }
+
+    //only the tests ask about single sections.
+    #[cfg(test)]
+    fn count(&self, section: i32) -> usize {
+        let i = self.segments.partition_point(|(s, _)| s.end < section);
+        match self.segments.get(i) {
+            Some((s, count)) if s.start <= section => *count,
+            _ => 0,
+        }
+    }
+
+    //merge the segments matching the predicate into as few ranges as possible.
+    fn ranges_where(&self, keep: impl Fn(usize) -> bool) -> Vec<Assignment> {
+        let mut ranges: Vec<Assignment> = Vec::new();
 
-            return (fully_overlapped, partial_overlap);
-        })
-        .reduce({
-            |mut results, current| {
-                results.0 += current.0;
-                results.1 += current.1;
-                results
+        for (segment, count) in &self.segments {
+            if !keep(*count) {
+                continue;
             }
-        });
+
+            match ranges.last_mut().and_then(|last| last.union(segment)) {
+                Some(merged) => *ranges.last_mut().unwrap() = merged,
+                None => ranges.push(*segment),
+            }
+        }
+
+        return ranges;
+    }
+
+    //sections between the lowest and highest assigned section that no elf covers.
+    fn uncovered(&self) -> Vec<Assignment> {
+        self.ranges_where(|count| count == 0)
+    }
+
+    fn covered_by_more_than(&self, n: usize) -> Vec<Assignment> {
+        self.ranges_where(|count| count > n)
+    }
+
+    //the two elves anywhere in the file whose assignments share the most sections.
+    fn most_redundant_pair(&self) -> Option<(ElfId, ElfId, usize)> {
+        let mut best: Option<(ElfId, ElfId, usize)> = None;
+
+        for (i, (me_id, me)) in self.elves.iter().enumerate() {
+            for (you_id, you) in &self.elves[i + 1..] {
+                let Some(shared) = me.intersection(you) else {
+                    continue;
+                };
+
+                if best.is_none_or(|(_, _, len)| shared.len() > len) {
+                    best = Some((*me_id, *you_id, shared.len()));
+                }
+            }
+        }
+
+        return best;
+    }
+
+    //elves whose every section is also covered by at least one other elf.
+    // that is, no section they cover is covered by them alone.
+    fn fully_covered_by_others(&self) -> Vec<(ElfId, Assignment)> {
+        let alone = self.ranges_where(|count| count == 1);
+
+        self.elves
+            .iter()
+            .filter(|(_, a)| {
+                let i = alone.partition_point(|r| r.end < a.start);
+                alone.get(i).is_none_or(|r| r.start > a.end)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+fn join(ranges: &[Assignment]) -> String {
+    if ranges.is_empty() {
+        return "none".to_string();
+    }
+
+    ranges
+        .iter()
+        .map(|r| r.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn report_coverage(pairs: &[(Assignment, Assignment)], n: usize) {
+    let coverage = Coverage::new(pairs);
+
+    println!("uncovered sections: {}", join(&coverage.uncovered()));
+    println!(
+        "sections covered by more than {} elves: {}",
+        n,
+        join(&coverage.covered_by_more_than(n))
+    );
+
+    if let Some((me, you, len)) = coverage.most_redundant_pair() {
+        println!(
+            "most redundant pair: line {} elf {} and line {} elf {} share {} sections",
+            me.0, me.1, you.0, you.1, len
+        );
+    }
+
+    let covered = coverage.fully_covered_by_others();
+    println!("{} assignments are fully covered by others:", covered.len());
+    for ((line, elf), a) in covered {
+        println!("  line {} elf {}: {}", line, elf, a);
+    }
+}
+
+//pass `coverage [N]` to analyse all assignments together, reporting sections covered by more than N elves.
+fn main() {
+    let input = fs::read_to_string("./4.input").expect("Error while reading");
+
+    let pairs = parse_input(&input).expect("could not parse assignments");
+
+    let mut args = std::env::args().skip(1);
+    if let Some(mode) = args.next() {
+        assert_eq!(mode, "coverage", "unknown mode {:?}", mode);
+
+        let n = args
+            .next()
+            .map(|n| n.parse().expect("N should be a number of elves"))
+            .unwrap_or(2);
+        report_coverage(&pairs, n);
+        return;
+    }
+
+    let total = count_overlaps(&pairs);
 
     println!("{:?}", total);
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const PROVIDED_INPUT: &str = "2-4,6-8
+2-3,4-5
+5-7,7-9
+2-8,3-7
+6-6,4-6
+2-6,4-8
+";
+
+    fn a(start: i32, end: i32) -> Assignment {
+        Assignment::new(start, end).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("2-4".parse::<Assignment>(), Ok(a(2, 4)));
+        assert_eq!(
+            "4-2".parse::<Assignment>(),
+            Err(AssignmentError::Reversed { start: 4, end: 2 })
+        );
+        assert_eq!(
+            "4".parse::<Assignment>(),
+            Err(AssignmentError::Malformed("4".to_string()))
+        );
+        assert_eq!(
+            "a-2".parse::<Assignment>(),
+            Err(AssignmentError::Malformed("a-2".to_string()))
+        );
+        assert!(parse_pair("2-4").is_err());
+        assert!(parse_input("2-4,6-8\n2-4;6-8\n")
+            .unwrap_err()
+            .starts_with("line 2"));
+    }
+
+    #[test]
+    fn test_set_operations() {
+        assert_eq!(a(2, 8).len(), 7);
+        assert_eq!(a(6, 6).len(), 1);
+
+        assert!(a(2, 8).contains(&a(3, 7)));
+        assert!(!a(3, 7).contains(&a(2, 8)));
+
+        assert!(a(5, 7).overlaps(&a(7, 9)));
+        assert!(!a(2, 3).overlaps(&a(4, 5)));
+
+        assert_eq!(a(2, 6).intersection(&a(4, 8)), Some(a(4, 6)));
+        assert_eq!(a(2, 3).intersection(&a(4, 5)), None);
+
+        assert_eq!(a(2, 6).union(&a(4, 8)), Some(a(2, 8)));
+        assert_eq!(a(2, 3).union(&a(4, 5)), Some(a(2, 5)));
+        assert_eq!(a(2, 3).union(&a(5, 6)), None);
+
+        let everything = a(i32::MIN, i32::MAX);
+        assert_eq!(everything.len(), 1 << 32);
+        assert_eq!(a(0, i32::MAX).len(), 1 << 31);
+        assert_eq!(a(5, i32::MAX).union(&a(i32::MIN, 4)), Some(everything));
+        assert_eq!(a(i32::MAX, i32::MAX).union(&a(1, 1)), None);
+    }
+
+    #[test]
+    fn test_provided_input() {
+        let pairs = parse_input(PROVIDED_INPUT).unwrap();
+        assert_eq!(count_overlaps(&pairs), (2, 4));
+    }
+
+    #[test]
+    fn test_coverage() {
+        let pairs = parse_input(PROVIDED_INPUT).unwrap();
+        let coverage = Coverage::new(&pairs);
+
+        assert_eq!(coverage.uncovered(), vec![]);
+        assert_eq!(coverage.count(1), 0);
+        assert_eq!(coverage.count(6), 8);
+        assert_eq!(coverage.count(10), 0);
+        assert_eq!(coverage.covered_by_more_than(4), vec![a(3, 7)]);
+
+        // 2-8 shares five sections with each of 3-7, 2-6 and 4-8. the first one found wins.
+        assert_eq!(coverage.most_redundant_pair(), Some(((4, 1), (4, 2), 5)));
+
+        // only 7-9 has a section nobody else covers.
+        let covered = coverage.fully_covered_by_others();
+        assert_eq!(covered.len(), 11);
+        assert!(!covered.contains(&((3, 2), a(7, 9))));
+    }
+
+    #[test]
+    fn test_huge_ranges() {
+        let pairs = parse_input("1-1500000000,1-1\n0-2147483647,1-1\n").unwrap();
+        let coverage = Coverage::new(&pairs);
+
+        assert_eq!(coverage.count(0), 1);
+        assert_eq!(coverage.count(1), 4);
+        assert_eq!(coverage.count(1500000000), 2);
+        assert_eq!(coverage.count(i32::MAX), 1);
+        assert_eq!(coverage.uncovered(), vec![]);
+        assert_eq!(coverage.covered_by_more_than(2), vec![a(1, 1)]);
+        assert_eq!(coverage.fully_covered_by_others().len(), 3);
+
+        let pairs = [(a(i32::MIN, 5), a(100, i32::MAX))];
+        let coverage = Coverage::new(&pairs);
+        assert_eq!(coverage.uncovered(), vec![a(6, 99)]);
+        assert_eq!(coverage.count(i32::MIN), 1);
+        assert_eq!(coverage.fully_covered_by_others(), vec![]);
+    }
+
+    #[test]
+    fn test_uncovered_gaps() {
+        let pairs = parse_input("1-2,3-3\n7-8,8-9\n").unwrap();
+        let coverage = Coverage::new(&pairs);
+
+        assert_eq!(coverage.uncovered(), vec![a(4, 6)]);
+        assert_eq!(coverage.covered_by_more_than(1), vec![a(8, 8)]);
+        assert_eq!(coverage.most_redundant_pair(), Some(((2, 1), (2, 2), 1)));
+        assert_eq!(coverage.fully_covered_by_others(), vec![]);
+    }
+}
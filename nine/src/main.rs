@@ -1,4 +1,11 @@
-use std::{collections::HashMap, fs};
+#![allow(clippy::needless_return)]
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::Path,
+};
 
 type Coord = (i32, i32);
 
@@ -33,110 +40,396 @@ impl Item {
 
         //we only move 0 or 1 steps towards our target, but we dont know the direction, so normalize by abs value.
         if diffx != 0 {
-            diffx = diffx / diffx.abs();
+            diffx /= diffx.abs();
         }
 
         if diffy != 0 {
-            diffy = diffy / diffy.abs();
+            diffy /= diffy.abs();
         }
 
         self.pos.0 += diffx;
         self.pos.1 += diffy;
-        return;
     }
 }
 
-fn debug(knots: &Vec<Item>) {
-    let mut positions: HashMap<Coord, &Item> = HashMap::new();
-    for knt in knots {
-        positions.insert(knt.pos, knt);
+/*
+    A rope of any number of knots, all starting on the same spot.
+    Every knot keeps its own set of the positions it has been on, so the tail's set answers
+        the puzzle and the others come for free.
+*/
+struct Rope {
+    knots: Vec<Item>,
+    visited: Vec<HashSet<Coord>>,
+}
+
+impl Rope {
+    fn new(num_knots: usize) -> Rope {
+        assert!(num_knots > 0, "a rope needs at least one knot");
+
+        //H for the head, T for the tail and the knot number in between. numbers past 9 wrap around.
+        let knots: Vec<Item> = (0..num_knots)
+            .map(|i| Item {
+                pos: (0, 0),
+                label: match i {
+                    0 => 'H',
+                    _ if i == num_knots - 1 => 'T',
+                    _ => char::from_digit((i % 10) as u32, 10).unwrap(),
+                },
+            })
+            .collect();
+
+        let visited = vec![HashSet::from([(0, 0)]); num_knots];
+
+        Rope { knots, visited }
     }
 
-    for y in (-15..30).rev() {
-        for x in (-15..27) {
-            if let Some(knt) = positions.get(&(x, y)) {
-                print!("{}", knt.label);
-            } else {
-                print!(".");
-            }
+    //move the head one step and let every other knot catch up with the one in front of it.
+    fn step(&mut self, unit: Coord) {
+        self.knots[0].update(unit);
+        self.visited[0].insert(self.knots[0].pos);
+
+        for i in 1..self.knots.len() {
+            let lead = self.knots[i - 1].pos;
+            self.knots[i].chase(lead);
+            self.visited[i].insert(self.knots[i].pos);
         }
-        print!("\n");
     }
-    print!("\n");
-}
 
-fn unit_vector(s: &str) -> Coord {
-    match s {
-        "R" => (1, 0),
-        "L" => (-1, 0),
-        "U" => (0, 1),
-        "D" => (0, -1),
-        _ => panic!("unknown direction"),
+    fn apply(&mut self, motion: &Motion) {
+        for _ in 0..motion.count {
+            self.step(motion.unit);
+        }
+    }
+
+    //number of different positions each knot has been on, head first.
+    fn visited_counts(&self) -> Vec<usize> {
+        self.visited.iter().map(|v| v.len()).collect()
+    }
+
+    fn tail_visited(&self) -> usize {
+        self.visited.last().unwrap().len()
     }
 }
 
-const NUM_KNOTS: u32 = 10;
-fn main() {
-    println!("Hello, world!");
+//the smallest box, corners inclusive, around a set of positions and the starting spot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bounds {
+    min: Coord,
+    max: Coord,
+}
 
-    let input = fs::read_to_string("./9.input").expect("Error while reading");
+impl Bounds {
+    fn around(positions: impl IntoIterator<Item = Coord>) -> Bounds {
+        let mut bounds = Bounds {
+            min: (0, 0),
+            max: (0, 0),
+        };
 
-    let lines = input.lines();
+        for (x, y) in positions {
+            bounds.min = (bounds.min.0.min(x), bounds.min.1.min(y));
+            bounds.max = (bounds.max.0.max(x), bounds.max.1.max(y));
+        }
 
-    let mut knots: Vec<Item> = Vec::new();
+        bounds
+    }
+}
 
-    for i in 0..(NUM_KNOTS) {
-        knots.push(Item {
-            pos: (0, 0),
-            label: i.to_string().chars().next().unwrap(), //beter way to get "1" ?
-        });
+impl Rope {
+    //everywhere any knot is or has been.
+    fn bounds(&self) -> Bounds {
+        Bounds::around(self.visited.iter().flatten().copied())
     }
 
-    let tail_pos = knots.len() - 1;
+    /*
+        Draws the rope the way the puzzle does, with up at the top. A knot hides the ones behind it,
+            the tail's trail is `#` when asked for, and `s` marks the start if nothing covers it.
+    */
+    fn render(&self, bounds: Bounds, knots: bool, trail: bool) -> String {
+        let mut labels: HashMap<Coord, char> = HashMap::new();
+        if knots {
+            for knot in self.knots.iter().rev() {
+                labels.insert(knot.pos, knot.label);
+            }
+        }
+        let tail = self.visited.last().unwrap();
+
+        let mut out = String::new();
+        for y in (bounds.min.1..=bounds.max.1).rev() {
+            for x in bounds.min.0..=bounds.max.0 {
+                out.push(match labels.get(&(x, y)) {
+                    Some(label) => *label,
+                    None if trail && tail.contains(&(x, y)) && (x, y) != (0, 0) => '#',
+                    None if (x, y) == (0, 0) => 's',
+                    None => '.',
+                });
+            }
+            out.push('\n');
+        }
 
-    if let Some(h) = knots.get_mut(0) {
-        h.label = 'H';
+        out
     }
 
-    if let Some(t) = knots.get_mut(tail_pos) {
-        t.label = 'T';
+    //just the tail's trail, sized to fit.
+    fn render_visited(&self) -> String {
+        self.render(
+            Bounds::around(self.visited.last().unwrap().iter().copied()),
+            false,
+            true,
+        )
     }
+}
 
-    let v = Item {
-        pos: (0, 0),
-        label: '#',
+/*
+    One text file per step, frame_00000.txt for the start and on from there, every `every` steps.
+    The rope is simulated twice: once to find how far it goes, so every frame shares one viewport
+        and they line up when played back, then again to draw it.
+*/
+fn write_frames(
+    motions: &[Motion],
+    num_knots: usize,
+    dir: &Path,
+    every: usize,
+) -> io::Result<usize> {
+    let bounds = simulate(motions, num_knots).bounds();
+    fs::create_dir_all(dir)?;
+
+    let mut rope = Rope::new(num_knots);
+    let mut written = 0;
+    let mut write_frame = |rope: &Rope, step: usize| -> io::Result<()> {
+        let mut out = fs::File::create(dir.join(format!("frame_{:05}.txt", step)))?;
+        out.write_all(rope.render(bounds, true, true).as_bytes())?;
+        written += 1;
+        Ok(())
     };
 
-    let mut visited: HashMap<Coord, &Item> = HashMap::new();
+    write_frame(&rope, 0)?;
+    let mut step = 0;
+    for motion in motions {
+        for _ in 0..motion.count {
+            rope.step(motion.unit);
+            step += 1;
+            if step % every.max(1) == 0 {
+                write_frame(&rope, step)?;
+            }
+        }
+    }
+
+    Ok(written)
+}
+
+fn unit_vector(s: &str) -> Option<Coord> {
+    match s {
+        "R" => Some((1, 0)),
+        "L" => Some((-1, 0)),
+        "U" => Some((0, 1)),
+        "D" => Some((0, -1)),
+        _ => None,
+    }
+}
+
+//one line of input: move the head count steps in the unit direction.
+#[derive(Debug, PartialEq)]
+struct Motion {
+    unit: Coord,
+    count: u32,
+}
 
-    for line in lines {
-        let mut parts = line.split(" ");
-        let dir = parts.next().unwrap();
-        let cnt = parts.next().unwrap().parse::<i32>().unwrap();
+fn parse_motions(input: &str) -> Result<Vec<Motion>, String> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let malformed = || format!("line {}: could not parse motion {:?}", i + 1, line);
 
-        println!("== {} {} == ", dir, cnt);
+            let (dir, cnt) = line.split_once(' ').ok_or_else(malformed)?;
+            let unit = unit_vector(dir).ok_or_else(malformed)?;
+            let count = cnt.parse::<u32>().map_err(|_| malformed())?;
 
-        let unit = unit_vector(dir);
-        for _ in 0..cnt {
-            let mut c: Coord = (0, 0);
+            Ok(Motion { unit, count })
+        })
+        .collect()
+}
 
-            if let Some(h) = knots.get_mut(0) {
-                h.update(unit);
-                c = h.pos;
-            }
+fn simulate(motions: &[Motion], num_knots: usize) -> Rope {
+    let mut rope = Rope::new(num_knots);
+    for motion in motions {
+        rope.apply(motion);
+    }
+    rope
+}
 
-            for k in &mut knots {
-                k.chase(c);
-                c = k.pos;
+/*
+    pass knot counts to simulate ropes of those lengths, e.g. `2 10` (the default) for both parts.
+    `show [KNOTS]` draws the rope after every motion, `map [KNOTS]` draws where its tail went,
+        and `frames DIR [KNOTS] [EVERY]` writes a frame every EVERY steps for animating. ropes have 10 knots unless told.
+*/
+fn main() {
+    let input = fs::read_to_string("./9.input").expect("Error while reading");
+    let motions = parse_motions(&input).unwrap_or_else(|e| panic!("{}", e));
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let number = |i: usize, default: usize| -> usize {
+        args.get(i)
+            .map(|n| {
+                n.parse()
+                    .ok()
+                    .filter(|c| *c > 0)
+                    .expect("knot counts and steps should be positive numbers")
+            })
+            .unwrap_or(default)
+    };
+
+    match args.first().map(|a| a.as_str()) {
+        Some("show") => {
+            let mut rope = Rope::new(number(1, 10));
+            for motion in &motions {
+                rope.apply(motion);
+                println!("{}", rope.render(rope.bounds(), true, false));
+            }
+        }
+        Some("map") => print!("{}", simulate(&motions, number(1, 10)).render_visited()),
+        Some("frames") => {
+            let dir = Path::new(args.get(1).expect("frames needs a directory to write to"));
+            let written = write_frames(&motions, number(2, 10), dir, number(3, 1))
+                .expect("could not write frames");
+            println!("wrote {} frames to {}", written, dir.display());
+        }
+        _ => {
+            let mut knot_counts: Vec<usize> = (0..args.len()).map(|i| number(i, 0)).collect();
+            if knot_counts.is_empty() {
+                knot_counts = vec![2, 10];
             }
 
-            if let Some(t) = knots.get(tail_pos) {
-                visited.insert(t.pos, &v);
+            for num_knots in knot_counts {
+                let rope = simulate(&motions, num_knots);
+
+                println!("{} knots: tail visited {}", num_knots, rope.tail_visited());
+                for (knot, count) in rope.knots.iter().zip(rope.visited_counts()) {
+                    println!("  {} visited {}", knot.label, count);
+                }
             }
         }
+    }
+}
 
-        debug(&knots);
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const SMALL_INPUT: &str = "R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2
+";
+
+    const LARGER_INPUT: &str = include_str!("../9.test");
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse_motions("R 4\nD 12\n").unwrap(),
+            vec![
+                Motion {
+                    unit: (1, 0),
+                    count: 4
+                },
+                Motion {
+                    unit: (0, -1),
+                    count: 12
+                }
+            ]
+        );
+        assert!(parse_motions("R 4\nX 1\n")
+            .unwrap_err()
+            .starts_with("line 2"));
+        assert!(parse_motions("R\n").is_err());
+        assert!(parse_motions("R 4\nL -3\n")
+            .unwrap_err()
+            .starts_with("line 2"));
     }
 
-    println!("{}", visited.len());
+    #[test]
+    fn test_provided_input() {
+        let small = parse_motions(SMALL_INPUT).unwrap();
+        assert_eq!(simulate(&small, 2).tail_visited(), 13);
+        assert_eq!(simulate(&small, 10).tail_visited(), 1);
+
+        let larger = parse_motions(LARGER_INPUT).unwrap();
+        assert_eq!(simulate(&larger, 10).tail_visited(), 36);
+    }
+
+    #[test]
+    fn test_every_knot_is_tracked() {
+        let small = parse_motions(SMALL_INPUT).unwrap();
+        let rope = simulate(&small, 10);
+
+        let counts = rope.visited_counts();
+        assert_eq!(counts.len(), 10);
+        // the second knot of a long rope moves just like the tail of a short one.
+        assert_eq!(counts[1], 13);
+        assert_eq!(counts[9], 1);
+        // knots further back never visit more places than the ones they follow.
+        assert!(counts.windows(2).all(|pair| pair[0] >= pair[1]));
+
+        let head = simulate(&small, 1);
+        assert_eq!(head.tail_visited(), counts[0]);
+    }
+
+    #[test]
+    fn test_labels() {
+        let labels: String = Rope::new(12).knots.iter().map(|k| k.label).collect();
+        assert_eq!(labels, "H1234567890T");
+        assert_eq!(Rope::new(1).knots[0].label, 'H');
+    }
+
+    #[test]
+    fn test_render() {
+        let small = parse_motions(SMALL_INPUT).unwrap();
+        let rope = simulate(&small, 2);
+
+        assert_eq!(
+            rope.bounds(),
+            Bounds {
+                min: (0, 0),
+                max: (5, 4)
+            }
+        );
+        assert_eq!(
+            rope.render(rope.bounds(), true, false),
+            "......\n......\n.TH...\n......\ns.....\n"
+        );
+        assert_eq!(rope.render_visited(), "..##.\n...##\n.####\n....#\ns###.\n");
+
+        // a rope that only went down and left still gets a box that fits.
+        let rope = simulate(&parse_motions("L 3\nD 2\n").unwrap(), 2);
+        assert_eq!(
+            rope.bounds(),
+            Bounds {
+                min: (-3, -2),
+                max: (0, 0)
+            }
+        );
+        assert_eq!(rope.render(rope.bounds(), true, true), ".##s\nT...\nH...\n");
+    }
+
+    #[test]
+    fn test_write_frames() {
+        let dir = std::env::temp_dir().join(format!("nine-frames-{}", std::process::id()));
+        let small = parse_motions(SMALL_INPUT).unwrap();
+
+        // 24 steps, plus the starting frame.
+        assert_eq!(write_frames(&small, 2, &dir, 1).unwrap(), 25);
+        assert_eq!(write_frames(&small, 2, &dir.join("sparse"), 10).unwrap(), 3);
+
+        let first = fs::read_to_string(dir.join("frame_00000.txt")).unwrap();
+        let last = fs::read_to_string(dir.join("frame_00024.txt")).unwrap();
+        assert_eq!(first, "......\n......\n......\n......\nH.....\n");
+        assert_eq!(last, "..##..\n...##.\n.TH##.\n....#.\ns###..\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
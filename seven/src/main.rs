@@ -1,5 +1,7 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
+use std::ops::RangeBounds;
 
 /*
     Our Domain has two data types: a Dir and File.
@@ -15,7 +17,6 @@ struct File {
     name: String,
 }
 
-#[derive(Debug, PartialEq)]
 struct Dir {
     name: String,
     files: Vec<File>,
@@ -34,179 +35,1370 @@ impl Dir {
     //size of all my files and that of all my children.
     fn size(&self) -> usize {
         let mut total = 0;
-        for f in &self.files {
-            total += f.size;
-        }
+        let mut stack = vec![self];
 
-        self.children.iter().for_each(|(_, d)| {
-            total += d.size();
-        });
+        while let Some(dir) = stack.pop() {
+            total += dir.files.iter().map(|f| f.size).sum::<usize>();
+            stack.extend(dir.children.values());
+        }
 
         total
     }
 
-    //Walk the dir try to find the sum of directories that have a transitive sum less than 100k
-    // return current branch disk size and current tally
-    fn aoc_dir_sum(&self) -> (usize, usize) {
-        let mut my_size = 0;
-        let mut dir_sum = 0;
+    //the total size of this directory and of every directory below it, this one first.
+    // walks with an explicit stack so a very deep tree can not overflow the real one.
+    fn dir_sizes(&self) -> Vec<usize> {
+        let mut sizes = Vec::new();
+        let mut parents = Vec::new();
+        let mut stack = vec![(self, 0)];
+
+        while let Some((dir, parent)) = stack.pop() {
+            let i = sizes.len();
+            sizes.push(dir.files.iter().map(|f| f.size).sum::<usize>());
+            parents.push(parent);
+            stack.extend(dir.children.values().map(|child| (child, i)));
+        }
 
-        for f in &self.files {
-            my_size += f.size;
+        //every directory comes after its parent, so going backwards adds each one in before its parent is used.
+        for i in (1..sizes.len()).rev() {
+            sizes[parents[i]] += sizes[i];
         }
 
-        self.children.iter().for_each(|(_, d)| {
-            let (kid_size, kid_sum) = d.aoc_dir_sum();
-            my_size += kid_size;
-            dir_sum += kid_sum;
-        });
+        sizes
+    }
 
-        if my_size <= 100000 {
-            dir_sum += my_size;
-        }
-        (my_size, dir_sum)
+    //find the sum of directories that have a transitive sum of at most max_size
+    // return this directory's size and that sum
+    fn aoc_dir_sum(&self, max_size: usize) -> (usize, usize) {
+        let sizes = self.dir_sizes();
+        let dir_sum = sizes.iter().filter(|size| **size <= max_size).sum();
+
+        (sizes[0], dir_sum)
     }
 
-    //Walk the dir try to find the smallest directory that, when deleted, will free min_size space.
-    // return current directory size and current winning size or 0 if none found yet.
+    //find the smallest directory that, when deleted, will free min_size space.
+    // return this directory's size and the winning size, or 0 if none is big enough.
     fn aoc_dir_size_min_above(&self, min_size: usize) -> (usize, usize) {
-        let mut my_size = 0;
-        let mut min_acceptable_so_far = 0;
+        let sizes = self.dir_sizes();
+        let min_acceptable = sizes
+            .iter()
+            .copied()
+            .filter(|size| *size > min_size)
+            .min()
+            .unwrap_or(0);
+
+        (sizes[0], min_acceptable)
+    }
+}
+
+//the derived drop would recurse once per level, so unhook the children and drop them one at a time.
+impl Drop for Dir {
+    fn drop(&mut self) {
+        let mut stack: Vec<Dir> = self.children.drain().map(|(_, d)| d).collect();
 
-        for f in &self.files {
-            my_size += f.size;
+        while let Some(mut dir) = stack.pop() {
+            stack.extend(dir.children.drain().map(|(_, d)| d));
         }
+    }
+}
 
-        self.children.iter().for_each(|(_, d)| {
-            let (kid_size, kid_min_dirsize) = d.aoc_dir_size_min_above(min_size);
-            my_size += kid_size;
+//derived comparisons would recurse once per level too, so compare pairs of directories off a stack.
+impl PartialEq for Dir {
+    fn eq(&self, other: &Dir) -> bool {
+        let mut stack = vec![(self, other)];
 
-            if kid_min_dirsize > 0
-                && (min_acceptable_so_far == 0 || kid_min_dirsize < min_acceptable_so_far)
-            {
-                min_acceptable_so_far = kid_min_dirsize;
+        while let Some((a, b)) = stack.pop() {
+            if a.name != b.name || a.files != b.files || a.children.len() != b.children.len() {
+                return false;
             }
-        });
+            for (name, child) in &a.children {
+                match b.children.get(name) {
+                    Some(theirs) => stack.push((child, theirs)),
+                    None => return false,
+                }
+            }
+        }
+
+        true
+    }
+}
+
+//every directory as (depth, name, files), parents before children and siblings by name, rather than a nesting as deep as the tree.
+impl fmt::Debug for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        let mut stack = vec![(0, self)];
+
+        while let Some((depth, dir)) = stack.pop() {
+            list.entry(&(depth, &dir.name, &dir.files));
 
-        if my_size > min_size && (min_acceptable_so_far == 0 || my_size < min_acceptable_so_far) {
-            min_acceptable_so_far = my_size;
+            let mut children: Vec<&Dir> = dir.children.values().collect();
+            children.sort_unstable_by(|a, b| b.name.cmp(&a.name));
+            stack.extend(children.into_iter().map(|child| (depth + 1, child)));
         }
 
-        (my_size, min_acceptable_so_far)
+        list.finish()
     }
 }
 
-use history::*;
+/*
+    Looking things up in a tree once it is built.
+    Paths are written from this directory down, like `a/e/i`; a leading `/` is allowed and ignored.
+    Full paths handed back always start with `/`.
+*/
+#[derive(Debug, PartialEq)]
+enum Node<'a> {
+    Dir(&'a Dir),
+    File(&'a File),
+}
+
+impl Dir {
+    fn find(&self, path: &str) -> Option<Node<'_>> {
+        let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        let Some(last) = segments.pop() else {
+            return Some(Node::Dir(self));
+        };
+
+        let mut dir = self;
+        for name in segments {
+            dir = dir.children.get(name)?;
+        }
+
+        if let Some(child) = dir.children.get(last) {
+            return Some(Node::Dir(child));
+        }
+        dir.files.iter().find(|f| f.name == last).map(Node::File)
+    }
+
+    //every directory and file below this one with its full path, sorted by path.
+    fn entries(&self) -> Vec<(String, Node<'_>)> {
+        let mut entries = Vec::new();
+        let mut stack = vec![(String::new(), self)];
+
+        while let Some((path, dir)) = stack.pop() {
+            for f in &dir.files {
+                entries.push((format!("{}/{}", path, f.name), Node::File(f)));
+            }
+            for (name, child) in &dir.children {
+                let child_path = format!("{}/{}", path, name);
+                entries.push((child_path.clone(), Node::Dir(child)));
+                stack.push((child_path, child));
+            }
+        }
+
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries
+    }
+
+    //every file below this one with its full path.
+    fn files_with_paths(&self) -> Vec<(String, &File)> {
+        self.entries()
+            .into_iter()
+            .filter_map(|(path, node)| match node {
+                Node::File(f) => Some((path, f)),
+                Node::Dir(_) => None,
+            })
+            .collect()
+    }
+
+    //files whose size is in the range, e.g. `root.files_sized(100000..)`.
+    fn files_sized(&self, sizes: impl RangeBounds<usize>) -> Vec<(String, &File)> {
+        self.files_with_paths()
+            .into_iter()
+            .filter(|(_, f)| sizes.contains(&f.size))
+            .collect()
+    }
+
+    //directories and files whose full path matches a glob like `**/*.txt`.
+    fn glob(&self, pattern: &str) -> Vec<(String, Node<'_>)> {
+        self.entries()
+            .into_iter()
+            .filter(|(path, _)| glob_match(pattern, path))
+            .collect()
+    }
+}
 
 /*
-the challenge that i struggled with was taking a vector of parsed command history lines
-    and turning this into a tree.
-I probably should have actually read about ownership and lifetimes before doing AoC in rust :D
-One of the challenges here is that not only do i want to build it recursively,
-    but I want to consume the stream of tokens in an inner invocation and then resume from
-    where i left off on the outer invocation. This forced me into a mutable mutable ref to an
-    iterator so i could lend it down the stack and recieve it back in order to continue.
-not sure if i could get away with eliding some of the hints, but this makes compiler happy :/
+    `*` matches any run of characters inside one path segment and `?` matches exactly one,
+    while a `**` segment matches any number of whole segments, including none.
+*/
+fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pattern, &path)
+}
 
+fn match_segments(pattern: &[&str], path: &[&str]) -> bool {
+    wildcard(
+        pattern,
+        path,
+        |p| *p == "**",
+        |p, s| match_name(p.as_bytes(), s.as_bytes()),
+    )
+}
+
+fn match_name(pattern: &[u8], name: &[u8]) -> bool {
+    wildcard(pattern, name, |p| *p == b'*', |p, c| p == c || *p == b'?')
+}
+
+/*
+    The matching both of the above share: a star stands for any run of items, anything else for one.
+    When something fails to match, only the latest star needs to take one more item and try again,
+        so this never recurses and never goes back further than that.
 */
-fn build<'h>(hist: &mut impl Iterator<Item = history::Line<'h>>, cwd: &mut Dir) {
-    while let Some(line) = hist.next() {
-        match line {
-            Line::Dir { name } => {
-                cwd.children.insert(name.to_string(), Dir::new(name));
-            }
+fn wildcard<P, T>(
+    pattern: &[P],
+    items: &[T],
+    is_star: impl Fn(&P) -> bool,
+    matches: impl Fn(&P, &T) -> bool,
+) -> bool {
+    let (mut p, mut i) = (0, 0);
+    //where the latest star is in the pattern, and where in items it stops taking.
+    let mut star: Option<(usize, usize)> = None;
+
+    while i < items.len() {
+        if p < pattern.len() && is_star(&pattern[p]) {
+            star = Some((p, i));
+            p += 1;
+        } else if p < pattern.len() && matches(&pattern[p], &items[i]) {
+            p += 1;
+            i += 1;
+        } else if let Some((star_at, taken_to)) = star {
+            star = Some((star_at, taken_to + 1));
+            p = star_at + 1;
+            i = taken_to + 1;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(is_star)
+}
+
+use history::*;
+
+#[derive(Debug, PartialEq)]
+enum BuildError {
+    UnknownCommand {
+        line: usize,
+        name: String,
+        arg: Option<String>,
+    },
+}
 
-            Line::File { name, size } => {
-                let file = File {
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::UnknownCommand { line, name, arg } => match arg {
+                Some(arg) => write!(f, "line {}: unsupported command {} {}", line, name, arg),
+                None => write!(f, "line {}: unsupported command {}", line, name),
+            },
+        }
+    }
+}
+
+/*
+    The tree while it is being built: every directory in one Vec, pointing at its parent and children
+        by index, so `cd ..` and `cd name` are a single step however deep the current directory is.
+    A directory is always added after its parent, which lets `finish` put the nested Dir back
+        together from the end without recursing.
+*/
+struct Arena {
+    //names and files. children stay in `children` until `finish`.
+    dirs: Vec<Dir>,
+    parents: Vec<usize>,
+    children: Vec<HashMap<String, usize>>,
+    //where each file name sits in its directory's files.
+    files: Vec<HashMap<String, usize>>,
+}
+
+impl Arena {
+    //moves everything already in root into the arena, so building onto a tree adds to it.
+    fn take(root: &mut Dir) -> Arena {
+        let mut arena = Arena {
+            dirs: vec![Dir {
+                name: root.name.clone(),
+                files: std::mem::take(&mut root.files),
+                children: HashMap::new(),
+            }],
+            parents: vec![0],
+            children: vec![HashMap::new()],
+            files: vec![],
+        };
+
+        let mut stack: Vec<(Dir, usize)> = root.children.drain().map(|(_, d)| (d, 0)).collect();
+        while let Some((mut dir, parent)) = stack.pop() {
+            let i = arena.dirs.len();
+            arena.children[parent].insert(dir.name.clone(), i);
+            stack.extend(dir.children.drain().map(|(_, d)| (d, i)));
+
+            arena.dirs.push(dir);
+            arena.parents.push(parent);
+            arena.children.push(HashMap::new());
+        }
+
+        arena.files = arena
+            .dirs
+            .iter()
+            .map(|dir| {
+                let names = dir.files.iter().enumerate();
+                names.map(|(i, f)| (f.name.clone(), i)).collect()
+            })
+            .collect();
+
+        arena
+    }
+
+    //the child called name, added if we have not seen it yet.
+    fn child(&mut self, dir: usize, name: &str) -> usize {
+        if let Some(i) = self.children[dir].get(name) {
+            return *i;
+        }
+
+        let i = self.dirs.len();
+        self.children[dir].insert(name.to_string(), i);
+        self.dirs.push(Dir::new(name));
+        self.parents.push(dir);
+        self.children.push(HashMap::new());
+        self.files.push(HashMap::new());
+        i
+    }
+
+    //listing the same directory twice should not count its files twice.
+    fn add_file(&mut self, dir: usize, name: &str, size: usize) {
+        let files = &mut self.dirs[dir].files;
+        match self.files[dir].get(name) {
+            Some(i) => files[*i].size = size,
+            None => {
+                self.files[dir].insert(name.to_string(), files.len());
+                files.push(File {
                     name: name.to_string(),
                     size,
-                };
+                });
+            }
+        }
+    }
+
+    //the full path of a directory, like `/a/e`.
+    fn path(&self, mut dir: usize) -> String {
+        let mut names = Vec::new();
+        while dir != 0 {
+            names.push(self.dirs[dir].name.as_str());
+            dir = self.parents[dir];
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
 
-                cwd.files.push(file);
+    //the full path of something called name in a directory.
+    fn path_to(&self, dir: usize, name: &str) -> String {
+        match dir {
+            0 => format!("/{}", name),
+            _ => format!("{}/{}", self.path(dir), name),
+        }
+    }
+
+    //apply a cd argument to the current directory. handles `/`, `..`, `.` and multi-segment paths like `/a/b` or `../c`.
+    // going up from the root stays at the root, like a shell does.
+    fn change_dir(&mut self, cwd: usize, path: &str) -> usize {
+        let mut cwd = if path.starts_with('/') { 0 } else { cwd };
+
+        for segment in path.split('/') {
+            cwd = match segment {
+                "" | "." => cwd,
+                ".." => self.parents[cwd],
+                name => self.child(cwd, name),
+            };
+        }
+
+        cwd
+    }
+
+    fn finish(mut self, root: &mut Dir) {
+        while self.dirs.len() > 1 {
+            let dir = self.dirs.pop().unwrap();
+            let parent = self.parents[self.dirs.len()];
+            self.dirs[parent].children.insert(dir.name.clone(), dir);
+        }
+
+        let mut top = self.dirs.pop().unwrap();
+        root.files = std::mem::take(&mut top.files);
+        root.children = std::mem::take(&mut top.children);
+    }
+}
+
+/*
+Replays the shell history into the tree under root.
+The current directory can be anywhere at any time, so `cd` is not limited to one level down or back up.
+A `cd` into a directory that has not been listed yet creates it: the cd worked, so it exists.
+Nothing here recurses, so transcripts nested hundreds of thousands of directories deep are fine.
+*/
+fn build<'h>(
+    hist: impl IntoIterator<Item = history::Line<'h>>,
+    root: &mut Dir,
+) -> Result<(), BuildError> {
+    let mut arena = Arena::take(root);
+    let mut cwd = 0;
+
+    for (i, line) in hist.into_iter().enumerate() {
+        match line {
+            Line::Dir { name } => {
+                arena.child(cwd, name);
             }
 
-            //this is only done once so we cheat and ignore it.
+            Line::File { name, size } => arena.add_file(cwd, name, size),
+
             Line::Command {
-                name: "cd",
-                arg: Some("/"),
-            }
-            | Line::Command {
                 name: "ls",
                 arg: None,
             } => {}
 
-            //pop the stack
-            Line::Command {
-                name: "cd",
-                arg: Some(".."),
-            } => return,
-
             Line::Command {
                 name: "cd",
-                arg: Some(name),
-            } => {
-                //get a mutable reference to the directory
-                let dir = cwd
-                    .children
-                    .get_mut(&name.to_string())
-                    .expect("trying to enter a directory that does not exist");
-
-                /* find the dir with the same name, then call build inside that dir with the remaining history.
-                when we've cd .. back to here, resume processing. */
-                build(hist, dir);
-            }
+                arg: Some(path),
+            } => cwd = arena.change_dir(cwd, path),
 
             Line::Command { name, arg } => {
-                panic!("Unsupported command: {:?} {:?}", name, arg);
+                arena.finish(root);
+                return Err(BuildError::UnknownCommand {
+                    line: i + 1,
+                    name: name.to_string(),
+                    arg: arg.map(|a| a.to_string()),
+                });
             }
         }
     }
+
+    arena.finish(root);
+    Ok(())
+}
+
+//the puzzle's numbers. the disk ones can be changed from the command line for `plan`.
+const AOC_DIR_SUM_MAX: usize = 100000;
+const DISK_CAPACITY: usize = 70000000;
+const REQUIRED_FREE: usize = 30000000;
+
+#[derive(Debug, Clone, Copy)]
+struct Disk {
+    capacity: usize,
+    required_free: usize,
 }
 
-fn aoc_min_delete(root: &Dir) -> usize {
-    /* The total disk space available to the filesystem is 70000000.
-    To run the update, you need unused space of at least 30000000.
+impl Disk {
+    //how much has to be deleted before there is required_free space. 0 if there is already enough.
+    fn shortfall(&self, used: usize) -> usize {
+        let current_free = self.capacity.saturating_sub(used);
+        self.required_free.saturating_sub(current_free)
+    }
+}
+
+fn aoc_min_delete(root: &Dir, disk: Disk) -> usize {
+    /* The total disk space available to the filesystem is disk.capacity.
+    To run the update, you need unused space of at least disk.required_free.
     You need to find a directory you can delete that will free up enough space to run the update.
 
     Find the smallest directory that, if deleted, would free up enough space on the filesystem to run the update.
     What is the total size of that directory? */
 
-    let du = root.size();
-
-    let current_free = 70000000 - du;
-    let min_free = 30000000;
-
-    let additional_to_delete = min_free - current_free;
+    let additional_to_delete = disk.shortfall(root.size());
 
     let (_, output) = root.aoc_dir_size_min_above(additional_to_delete);
 
     output
 }
 
+/*
+    with no arguments, prints the two AoC answers.
+    `tree` prints the whole tree with sizes, `du` lists every directory largest first,
+    and `top [N]` lists the N largest files (10 by default).
+    `plan [bytes|dirs] [capacity] [required]` picks directories to delete to make room for the update.
+    `find [glob] [min] [max]` lists the paths matching a glob where `**` stands for any number of directories.
+    given sizes, only files in that range are listed.
+    `transcript` writes the tree back out as a tidy `$ cd` / `$ ls` session, and `json` writes it as JSON.
+    starting with `--json FILE` loads the tree from JSON instead of reading 7.input, before any of the above.
+*/
 fn main() {
-    let input = fs::read_to_string("./7.input").expect("Error while reading");
-    let result = history::parse_input(&input);
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+
+    let root = if args.first().map(|a| a.as_str()) == Some("--json") {
+        let path = args.get(1).expect("--json needs a file to load").clone();
+        args.drain(..2);
+
+        let input = fs::read_to_string(path).expect("Error while reading");
+        json::from_json(&input).unwrap_or_else(|e| panic!("{}", e))
+    } else {
+        let input = fs::read_to_string("./7.input").expect("Error while reading");
+        let result = history::parse_input(&input);
+
+        let mut root = Dir {
+            name: "/".to_string(),
+            files: Vec::new(),
+            children: HashMap::new(),
+        };
 
-    let mut root = Dir {
-        name: "/".to_string(),
-        files: Vec::new(),
-        children: HashMap::new(),
+        let h = result.unwrap().1;
+        build(h, &mut root).unwrap_or_else(|e| panic!("{}", e));
+        root
     };
 
-    let h = result.unwrap().1;
-    let mut hist = h.into_iter();
-    build(&mut hist, &mut root);
+    if let Some(mode) = args.first() {
+        let usage = report::Usage::new(&root);
+
+        let output = match mode.as_str() {
+            "tree" => report::tree(&usage),
+            "du" => report::du(&usage),
+            "top" => {
+                let n = args
+                    .get(1)
+                    .map(|n| n.parse().expect("N should be a number of files"))
+                    .unwrap_or(10);
+                report::largest_files(&usage, n)
+            }
+            "plan" => {
+                let minimize = match args.get(1).map(|m| m.as_str()) {
+                    Some("bytes") | None => planner::Minimize::Bytes,
+                    Some("dirs") => planner::Minimize::Directories,
+                    Some(m) => panic!("can only minimize bytes or dirs, not {:?}", m),
+                };
+                let number = |i: usize, default: usize| {
+                    args.get(i)
+                        .map(|n| n.parse().expect("disk sizes should be numbers"))
+                        .unwrap_or(default)
+                };
+                let disk = Disk {
+                    capacity: number(2, DISK_CAPACITY),
+                    required_free: number(3, REQUIRED_FREE),
+                };
+
+                match planner::plan_deletion(&usage, disk, minimize) {
+                    Ok(plan) => plan.to_string(),
+                    Err(e) => format!("{}\n", e),
+                }
+            }
+            "transcript" => history::write_transcript(&root),
+            "json" => json::to_json(&usage),
+            "find" => {
+                let pattern = args.get(1).map(|p| p.as_str()).unwrap_or("**");
+                let number = |i: usize, default: usize| {
+                    args.get(i)
+                        .map(|n| n.parse().expect("sizes should be numbers"))
+                        .unwrap_or(default)
+                };
+                let sizes = number(2, 0)..=number(3, usize::MAX);
+
+                let line = |path: &str, node: &Node| match node {
+                    Node::File(f) => format!("{}\t{}\n", f.size, path),
+                    Node::Dir(_) => format!("dir\t{}\n", path),
+                };
 
-    let (_, output) = root.aoc_dir_sum();
+                if args.len() > 2 {
+                    //a size range only makes sense for files.
+                    root.files_sized(sizes)
+                        .into_iter()
+                        .filter(|(path, _)| glob_match(pattern, path))
+                        .map(|(path, f)| line(&path, &Node::File(f)))
+                        .collect()
+                } else if !pattern.contains(['*', '?']) {
+                    match root.find(pattern) {
+                        Some(node) => line(pattern, &node),
+                        None => format!("{} not found\n", pattern),
+                    }
+                } else {
+                    root.glob(pattern)
+                        .iter()
+                        .map(|(path, node)| line(path, node))
+                        .collect()
+                }
+            }
+            _ => panic!(
+                "unknown mode {:?}, expected tree, du, top, plan, find, transcript or json",
+                mode
+            ),
+        };
+
+        print!("{}", output);
+        return;
+    }
+
+    let (_, output) = root.aoc_dir_sum(AOC_DIR_SUM_MAX);
     println!("{:?}", output);
 
-    let output = aoc_min_delete(&root);
+    let disk = Disk {
+        capacity: DISK_CAPACITY,
+        required_free: REQUIRED_FREE,
+    };
+    let output = aoc_min_delete(&root, disk);
     println!("{:?}", output);
 }
 
+/* du and tree style views of a Dir. */
+mod report {
+    use crate::Dir;
+
+    /*
+        A Dir with the size of everything below it worked out once, in a single walk,
+        so the reports can ask for sizes as often as they like.
+        Children are sorted by name so the output does not depend on HashMap order.
+    */
+    pub struct Usage<'a> {
+        pub dir: &'a Dir,
+        pub size: usize,
+        pub children: Vec<Usage<'a>>,
+    }
+
+    impl<'a> Usage<'a> {
+        //lists every directory first, then puts them together from the bottom up, so nothing recurses however deep the tree.
+        pub fn new(root: &'a Dir) -> Usage<'a> {
+            let mut order: Vec<(&'a Dir, usize)> = Vec::new();
+            let mut stack = vec![(root, 0)];
+            while let Some((dir, parent)) = stack.pop() {
+                let i = order.len();
+                order.push((dir, parent));
+
+                let mut children: Vec<&Dir> = dir.children.values().collect();
+                children.sort_by(|a, b| b.name.cmp(&a.name));
+                stack.extend(children.into_iter().map(|child| (child, i)));
+            }
+
+            //a directory's children are all later in the order, so they are finished before it is.
+            let mut finished: Vec<Vec<Usage<'a>>> = order.iter().map(|_| Vec::new()).collect();
+            for i in (0..order.len()).rev() {
+                let (dir, parent) = order[i];
+                let mut children = std::mem::take(&mut finished[i]);
+                children.reverse();
+
+                let size = dir.files.iter().map(|f| f.size).sum::<usize>()
+                    + children.iter().map(|c| c.size).sum::<usize>();
+                let usage = Usage {
+                    dir,
+                    size,
+                    children,
+                };
+
+                if i == 0 {
+                    return usage;
+                }
+                finished[parent].push(usage);
+            }
+            unreachable!("the root is always in the order")
+        }
+
+        //this directory and every directory below it with their full paths, parents before children.
+        pub fn dirs(&self) -> Vec<(String, &Usage<'a>)> {
+            let mut dirs = Vec::new();
+            let mut stack = vec![("/".to_string(), self)];
+
+            while let Some((path, usage)) = stack.pop() {
+                for child in usage.children.iter().rev() {
+                    stack.push((join(&path, &child.dir.name), child));
+                }
+                dirs.push((path, usage));
+            }
+
+            dirs
+        }
+    }
+
+    //the derived drop would recurse once per level, so unhook the children and drop them one at a time.
+    impl Drop for Usage<'_> {
+        fn drop(&mut self) {
+            let mut stack = std::mem::take(&mut self.children);
+
+            while let Some(mut usage) = stack.pop() {
+                stack.append(&mut usage.children);
+            }
+        }
+    }
+
+    fn join(path: &str, name: &str) -> String {
+        if path.ends_with('/') {
+            format!("{}{}", path, name)
+        } else {
+            format!("{}/{}", path, name)
+        }
+    }
+
+    //sizes like `du -h`: plain bytes below 1K, otherwise rounded up, with one decimal below 10.
+    pub fn human(size: usize) -> String {
+        let units = ["K", "M", "G", "T", "P"];
+
+        if size < 1024 {
+            return size.to_string();
+        }
+
+        let mut value = size as f64 / 1024.0;
+        let mut unit = 0;
+        while value >= 1024.0 && unit < units.len() - 1 {
+            value /= 1024.0;
+            unit += 1;
+        }
+
+        if value < 10.0 {
+            format!("{:.1}{}", (value * 10.0).ceil() / 10.0, units[unit])
+        } else {
+            format!("{}{}", value.ceil(), units[unit])
+        }
+    }
+
+    //like `tree --du -h`: directories and files sorted by name, each with its size.
+    pub fn tree(usage: &Usage) -> String {
+        let mut out = format!("[{:>5}]  /\n", human(usage.size));
+
+        //lines still to write, the next one last: the prefix to draw, the entry, and whether it ends its directory.
+        let mut pending: Vec<(String, &str, usize, Option<&Usage>, bool)> = Vec::new();
+        push_entries(usage, "", &mut pending);
+
+        while let Some((prefix, name, size, child, last)) = pending.pop() {
+            let branch = if last { "└── " } else { "├── " };
+            out.push_str(&format!(
+                "{}{}[{:>5}]  {}\n",
+                prefix,
+                branch,
+                human(size),
+                name
+            ));
+
+            if let Some(child) = child {
+                let deeper = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                push_entries(child, &deeper, &mut pending);
+            }
+        }
+
+        out
+    }
+
+    //the directories and files in usage, in reverse so the first one is popped first.
+    fn push_entries<'u>(
+        usage: &'u Usage,
+        prefix: &str,
+        pending: &mut Vec<(String, &'u str, usize, Option<&'u Usage<'u>>, bool)>,
+    ) {
+        let mut entries: Vec<(&str, usize, Option<&Usage>)> = usage
+            .children
+            .iter()
+            .map(|c| (c.dir.name.as_str(), c.size, Some(c)))
+            .chain(
+                usage
+                    .dir
+                    .files
+                    .iter()
+                    .map(|f| (f.name.as_str(), f.size, None)),
+            )
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+
+        let count = entries.len();
+        for (i, (name, size, child)) in entries.into_iter().enumerate().rev() {
+            pending.push((prefix.to_string(), name, size, child, i == count - 1));
+        }
+    }
+
+    //like `du -h | sort -rh`: every directory, largest first.
+    pub fn du(usage: &Usage) -> String {
+        let mut dirs = usage.dirs();
+        dirs.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(&b.0)));
+
+        dirs.iter()
+            .map(|(path, d)| format!("{}\t{}\n", human(d.size), path))
+            .collect()
+    }
+
+    //the n largest files anywhere in the tree, with their full paths.
+    pub fn largest_files(usage: &Usage, n: usize) -> String {
+        let mut files: Vec<(usize, String)> = usage
+            .dirs()
+            .iter()
+            .flat_map(|(path, d)| d.dir.files.iter().map(|f| (f.size, join(path, &f.name))))
+            .collect();
+        files.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+
+        files
+            .iter()
+            .take(n)
+            .map(|(size, path)| format!("{}\t{}\n", human(*size), path))
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::report::*;
+        use crate::test_fixtures::*;
+
+        #[test]
+        fn test_human() {
+            assert_eq!(human(584), "584");
+            assert_eq!(human(1024), "1.0K");
+            assert_eq!(human(2557), "2.5K");
+            assert_eq!(human(94853), "93K");
+            assert_eq!(human(48381165), "47M");
+        }
+
+        #[test]
+        fn test_usage_sizes() {
+            let root = provided_root();
+            let usage = Usage::new(&root);
+
+            let sizes: Vec<(String, usize)> = usage
+                .dirs()
+                .into_iter()
+                .map(|(path, d)| (path, d.size))
+                .collect();
+            assert_eq!(
+                sizes,
+                vec![
+                    ("/".to_string(), 48381165),
+                    ("/a".to_string(), 94853),
+                    ("/a/e".to_string(), 584),
+                    ("/d".to_string(), 24933642)
+                ]
+            );
+        }
+
+        #[test]
+        fn test_tree() {
+            let root = provided_root();
+
+            assert_eq!(
+                tree(&Usage::new(&root)),
+                "[  47M]  /
+├── [  93K]  a
+│   ├── [  584]  e
+│   │   └── [  584]  i
+│   ├── [  29K]  f
+│   ├── [ 2.5K]  g
+│   └── [  62K]  h.lst
+├── [  15M]  b.txt
+├── [ 8.2M]  c.dat
+└── [  24M]  d
+    ├── [ 5.4M]  d.ext
+    ├── [ 7.7M]  d.log
+    ├── [ 3.9M]  j
+    └── [ 6.9M]  k
+"
+            );
+        }
+
+        #[test]
+        fn test_du_and_largest_files() {
+            let root = provided_root();
+            let usage = Usage::new(&root);
+
+            assert_eq!(du(&usage), "47M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n");
+            assert_eq!(
+                largest_files(&usage, 3),
+                "15M\t/b.txt\n8.2M\t/c.dat\n7.7M\t/d/d.log\n"
+            );
+        }
+    }
+}
+
+/*
+    Picks several directories to delete when one is not the whole story: either deleting as few bytes
+    as possible, or as few directories as possible (and then as few bytes as possible among those).
+    The root can not be deleted, and a directory is never picked alongside one of its ancestors,
+    since that would count its bytes twice.
+*/
+mod planner {
+    use crate::report::Usage;
+    use crate::Disk;
+    use std::collections::HashSet;
+    use std::fmt;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Minimize {
+        Bytes,
+        Directories,
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub struct Plan {
+        pub dirs: Vec<(String, usize)>,
+        pub freed: usize,
+    }
+
+    impl fmt::Display for Plan {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            for (path, size) in &self.dirs {
+                writeln!(f, "{}\t{}", size, path)?;
+            }
+            writeln!(
+                f,
+                "{}\tfreed by {} directories",
+                self.freed,
+                self.dirs.len()
+            )
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    pub enum PlanError {
+        NotEnough,
+        GaveUp { steps: usize },
+    }
+
+    impl fmt::Display for PlanError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                PlanError::NotEnough => write!(f, "no set of directories frees enough space"),
+                PlanError::GaveUp { steps } => write!(
+                    f,
+                    "gave up after {} steps, there are too many ways to pick directories",
+                    steps
+                ),
+            }
+        }
+    }
+
+    //enough for any real filesystem we've seen, and few enough that the search stays in memory.
+    const MAX_STEPS: usize = 5_000_000;
+
+    pub fn plan_deletion(usage: &Usage, disk: Disk, minimize: Minimize) -> Result<Plan, PlanError> {
+        plan_within(usage, disk, minimize, MAX_STEPS)
+    }
+
+    fn plan_within(
+        usage: &Usage,
+        disk: Disk,
+        minimize: Minimize,
+        max_steps: usize,
+    ) -> Result<Plan, PlanError> {
+        let needed = disk.shortfall(usage.size);
+        if needed == 0 {
+            return Ok(Plan {
+                dirs: vec![],
+                freed: 0,
+            });
+        }
+
+        let dirs = flatten(usage);
+        let most = most_freed(&dirs, needed, minimize, max_steps)?;
+        let picked = search(&dirs, &most, needed, minimize, max_steps)?;
+
+        Ok(Plan {
+            freed: picked.iter().map(|i| dirs[*i].size).sum(),
+            dirs: picked
+                .into_iter()
+                .map(|i| (path(&dirs, i), dirs[i].size))
+                .collect(),
+        })
+    }
+
+    /* most[m][i]: the most that deleting at most m directories from dirs[i..] can free.
+    minimizing bytes doesn't care how many, so it only has the one column, without a limit.
+    minimizing directories adds columns until one frees enough, which is then as few as will do. */
+    fn most_freed(
+        dirs: &[Entry],
+        needed: usize,
+        minimize: Minimize,
+        max_steps: usize,
+    ) -> Result<Vec<Vec<usize>>, PlanError> {
+        let column = |fewer: Option<&[usize]>| {
+            let mut most = vec![0; dirs.len() + 1];
+            for (i, entry) in dirs.iter().enumerate().rev() {
+                let deleted = entry.size + fewer.map_or(most[entry.end], |fewer| fewer[entry.end]);
+                most[i] = most[i + 1].max(deleted);
+            }
+            most
+        };
+
+        let unlimited = column(None);
+        if unlimited[0] < needed {
+            return Err(PlanError::NotEnough);
+        }
+        if minimize == Minimize::Bytes {
+            return Ok(vec![unlimited]);
+        }
+
+        let mut most = vec![vec![0; dirs.len() + 1]];
+        while most[most.len() - 1][0] < needed {
+            if most.len() * (dirs.len() + 1) > max_steps {
+                return Err(PlanError::GaveUp {
+                    steps: most.len() * (dirs.len() + 1),
+                });
+            }
+            most.push(column(Some(&most[most.len() - 1])));
+        }
+        Ok(most)
+    }
+
+    enum Step {
+        Visit {
+            i: usize,
+            left: usize,
+            freed: usize,
+        },
+        Delete {
+            i: usize,
+            left: usize,
+            freed: usize,
+        },
+        Undelete,
+        //nothing under this visit beat the best plan from before it, so don't look again.
+        Failed {
+            i: usize,
+            left: usize,
+            freed: usize,
+            best: usize,
+        },
+    }
+
+    /* branch and bound down dirs, keeping each directory before trying to delete it,
+        with `left` directories still allowed to be deleted when minimizing those.
+    gives up on a branch once it can't free enough, or can't beat the best plan so far,
+        and stops altogether once a plan frees exactly what is needed. */
+    fn search(
+        dirs: &[Entry],
+        most: &[Vec<usize>],
+        needed: usize,
+        minimize: Minimize,
+        max_steps: usize,
+    ) -> Result<Vec<usize>, PlanError> {
+        let mut best: Option<(usize, Vec<usize>)> = None;
+        let mut picked = Vec::new();
+        let mut failed: HashSet<(usize, usize, usize)> = HashSet::new();
+        let mut steps = 0;
+        let mut stack = vec![Step::Visit {
+            i: 0,
+            left: most.len() - 1,
+            freed: 0,
+        }];
+
+        while let Some(step) = stack.pop() {
+            let bound = best.as_ref().map_or(usize::MAX, |(freed, _)| *freed);
+            if bound == needed {
+                break;
+            }
+
+            match step {
+                Step::Visit { i, left, freed } => {
+                    steps += 1;
+                    if steps > max_steps {
+                        return Err(PlanError::GaveUp { steps: max_steps });
+                    }
+
+                    if freed >= needed {
+                        if freed < bound {
+                            best = Some((freed, picked.clone()));
+                        }
+                    } else if i < dirs.len()
+                        && freed + most[left][i] >= needed
+                        && !failed.contains(&(i, left, freed))
+                    {
+                        stack.push(Step::Failed {
+                            i,
+                            left,
+                            freed,
+                            best: bound,
+                        });
+                        stack.push(Step::Delete { i, left, freed });
+                        stack.push(Step::Visit {
+                            i: i + 1,
+                            left,
+                            freed,
+                        });
+                    }
+                }
+                Step::Delete { i, left, freed } => {
+                    let Entry { size, end, .. } = dirs[i];
+                    if freed + size < bound {
+                        picked.push(i);
+                        stack.push(Step::Undelete);
+                        stack.push(Step::Visit {
+                            i: end,
+                            left: match minimize {
+                                Minimize::Bytes => left,
+                                Minimize::Directories => left - 1,
+                            },
+                            freed: freed + size,
+                        });
+                    }
+                }
+                Step::Undelete => {
+                    picked.pop();
+                }
+                Step::Failed {
+                    i,
+                    left,
+                    freed,
+                    best,
+                } => {
+                    if bound == best {
+                        failed.insert((i, left, freed));
+                    }
+                }
+            }
+        }
+
+        //most_freed already checked something frees enough, and nothing frees enough without being found.
+        Ok(best.expect("a plan that frees enough").1)
+    }
+
+    /* every directory below the root in preorder, with the index just past its subtree.
+    walking this list, each directory is either deleted (skip to the end of its subtree)
+        or kept (move on to the next one, which may be one of its children). */
+    struct Entry<'a> {
+        name: &'a str,
+        size: usize,
+        end: usize,
+        //None for the directories right below the root.
+        parent: Option<usize>,
+    }
+
+    fn flatten<'a>(usage: &'a Usage) -> Vec<Entry<'a>> {
+        let mut dirs: Vec<Entry> = Vec::new();
+        let mut stack: Vec<(&Usage, Option<usize>)> =
+            usage.children.iter().rev().map(|c| (c, None)).collect();
+
+        while let Some((usage, parent)) = stack.pop() {
+            let i = dirs.len();
+            dirs.push(Entry {
+                name: &usage.dir.name,
+                size: usage.size,
+                end: i + 1,
+                parent,
+            });
+            stack.extend(usage.children.iter().rev().map(|c| (c, Some(i))));
+        }
+
+        //a subtree ends where its last directory's does, and every directory comes after its parent.
+        for i in (0..dirs.len()).rev() {
+            if let Some(parent) = dirs[i].parent {
+                dirs[parent].end = dirs[parent].end.max(dirs[i].end);
+            }
+        }
+
+        dirs
+    }
+
+    fn path(dirs: &[Entry], mut i: usize) -> String {
+        let mut names = vec![dirs[i].name];
+        while let Some(parent) = dirs[i].parent {
+            names.push(dirs[parent].name);
+            i = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::planner::*;
+        use crate::test_fixtures::*;
+        use crate::*;
+        use proptest::prelude::*;
+
+        fn disk(capacity: usize, required_free: usize) -> Disk {
+            Disk {
+                capacity,
+                required_free,
+            }
+        }
+
+        #[test]
+        fn test_single_directory() {
+            let root = provided_root();
+            let usage = report::Usage::new(&root);
+            let plan = plan_deletion(&usage, disk(70000000, 30000000), Minimize::Bytes).unwrap();
+
+            assert_eq!(plan.dirs, vec![("/d".to_string(), 24933642)]);
+            assert_eq!(plan.freed, 24933642);
+        }
+
+        #[test]
+        fn test_already_enough_space() {
+            let root = provided_root();
+            let usage = report::Usage::new(&root);
+            let plan = plan_deletion(&usage, disk(100000000, 100), Minimize::Bytes).unwrap();
+
+            assert_eq!(plan.dirs, vec![]);
+        }
+
+        #[test]
+        fn test_impossible() {
+            let root = provided_root();
+            let usage = report::Usage::new(&root);
+
+            //only 25028495 is in directories, the rest are files in the root.
+            assert_eq!(
+                plan_deletion(&usage, disk(48381165, 30000000), Minimize::Bytes),
+                Err(PlanError::NotEnough)
+            );
+            assert_eq!(
+                plan_deletion(&usage, disk(48381165, 25028495), Minimize::Directories)
+                    .unwrap()
+                    .dirs
+                    .len(),
+                2
+            );
+        }
+
+        #[test]
+        fn test_bytes_versus_directories() {
+            let root = build_str(
+                "$ cd /
+$ ls
+dir a
+dir b
+dir c
+$ cd a
+$ ls
+40 x
+$ cd ../b
+$ ls
+35 y
+$ cd ../c
+$ ls
+100 z
+",
+            );
+            let usage = report::Usage::new(&root);
+
+            //175 used out of 200, so 50 more has to go for 75 free.
+            let plan = plan_deletion(&usage, disk(200, 75), Minimize::Bytes).unwrap();
+            assert_eq!(
+                plan.dirs,
+                vec![("/a".to_string(), 40), ("/b".to_string(), 35)]
+            );
+            assert_eq!(plan.freed, 75);
+
+            let plan = plan_deletion(&usage, disk(200, 75), Minimize::Directories).unwrap();
+            assert_eq!(plan.dirs, vec![("/c".to_string(), 100)]);
+        }
+
+        #[test]
+        fn test_never_deletes_a_directory_and_its_parent() {
+            let root = build_str(
+                "$ cd /
+$ ls
+dir a
+$ cd a
+$ ls
+dir b
+10 x
+$ cd b
+$ ls
+20 y
+",
+            );
+            let usage = report::Usage::new(&root);
+
+            let plan = plan_deletion(&usage, disk(30, 25), Minimize::Bytes).unwrap();
+            assert_eq!(plan.dirs, vec![("/a".to_string(), 30)]);
+
+            let plan = plan_deletion(&usage, disk(30, 15), Minimize::Bytes).unwrap();
+            assert_eq!(plan.dirs, vec![("/a/b".to_string(), 20)]);
+        }
+
+        //how much there is to search depends on how many directories there are, not how big they are.
+        #[test]
+        fn test_gigabytes() {
+            let root = json::from_json(
+                r#"[{"name": "/"},
+                    {"name": "a", "parent": 0, "files": [{"name": "f", "size": 5000000000}]},
+                    {"name": "b", "parent": 0, "files": [{"name": "g", "size": 6000000000}]}]"#,
+            )
+            .unwrap();
+            let usage = report::Usage::new(&root);
+
+            for minimize in [Minimize::Bytes, Minimize::Directories] {
+                let plan = plan_deletion(&usage, disk(12000000000, 6500000000), minimize).unwrap();
+                assert_eq!(plan.dirs, vec![("/b".to_string(), 6000000000)]);
+
+                let plan = plan_deletion(&usage, disk(12000000000, 12000000000), minimize).unwrap();
+                assert_eq!(plan.freed, 11000000000);
+                assert_eq!(plan.dirs.len(), 2);
+            }
+        }
+
+        #[test]
+        fn test_gives_up() {
+            //twenty directories of even sizes can never free exactly an odd number of bytes.
+            let transcript: String = (1..=20)
+                .map(|i| format!("$ cd /d{}\n$ ls\n{} f\n", i, i * 2))
+                .collect();
+            let root = build_str(&format!("$ cd /\n{}", transcript));
+            let usage = report::Usage::new(&root);
+            let disk = disk(usage.size, 201);
+
+            assert_eq!(
+                plan_within(&usage, disk, Minimize::Bytes, 100),
+                Err(PlanError::GaveUp { steps: 100 })
+            );
+            assert_eq!(
+                plan_deletion(&usage, disk, Minimize::Bytes).unwrap().freed,
+                202
+            );
+        }
+
+        //every set of directories without an ancestor in it, tried one by one.
+        fn brute_force(
+            usage: &report::Usage,
+            needed: usize,
+            minimize: Minimize,
+        ) -> Option<(usize, usize)> {
+            let dirs: Vec<_> = usage.dirs().into_iter().skip(1).collect();
+            let inside = |a: &str, b: &str| b.starts_with(a) && b[a.len()..].starts_with('/');
+
+            (0..1u32 << dirs.len())
+                .map(|set| {
+                    (0..dirs.len())
+                        .filter(|i| set & (1 << i) != 0)
+                        .collect::<Vec<_>>()
+                })
+                .filter(|picked| {
+                    picked
+                        .iter()
+                        .all(|a| picked.iter().all(|b| !inside(&dirs[*a].0, &dirs[*b].0)))
+                })
+                .map(|picked| (picked.len(), picked.iter().map(|i| dirs[*i].1.size).sum()))
+                .filter(|(_, freed)| *freed >= needed)
+                .min_by_key(|(count, freed)| match minimize {
+                    Minimize::Bytes => (0, *freed),
+                    Minimize::Directories => (*count, *freed),
+                })
+        }
+
+        proptest! {
+            #[test]
+            fn test_matches_brute_force(
+                entries in prop::collection::vec((prop::collection::vec("[a-c]", 1..4), 1usize..1000), 0..6),
+                needed in 1usize..3000,
+            ) {
+                let mut transcript = String::new();
+                for (path, size) in &entries {
+                    transcript.push_str(&format!("$ cd /{}\n$ ls\n{} f\n", path.join("/"), size));
+                }
+                let root = build_str(&format!("$ cd /\n{}", transcript));
+                let usage = report::Usage::new(&root);
+                prop_assume!(usage.dirs().len() <= 16);
+
+                for minimize in [Minimize::Bytes, Minimize::Directories] {
+                    let plan = match plan_deletion(&usage, disk(usage.size, needed), minimize) {
+                        Err(PlanError::NotEnough) => None,
+                        plan => Some(plan.unwrap()),
+                    };
+                    let expected = brute_force(&usage, needed, minimize);
+
+                    let got = plan.map(|plan| {
+                        let picked: usize = plan.dirs.iter().map(|(_, size)| size).sum();
+                        assert_eq!(picked, plan.freed);
+                        (plan.dirs.len(), plan.freed)
+                    });
+                    match minimize {
+                        Minimize::Bytes => prop_assert_eq!(got.map(|g| g.1), expected.map(|e| e.1)),
+                        Minimize::Directories => prop_assert_eq!(got, expected),
+                    }
+                }
+            }
+        }
+    }
+}
+
 /* represents the file input. */
 mod history {
 
+    use crate::Dir;
     use nom::{
         branch::alt,
         bytes::complete::{is_a, is_not, tag},
@@ -223,7 +1415,7 @@ mod history {
         Dir { name: &'a str },
     }
 
-    fn parse_command(i: &str) -> IResult<&str, Line> {
+    fn parse_command(i: &str) -> IResult<&str, Line<'_>> {
         let name = preceded(tag("$ "), is_not(" \n"));
         let arg = preceded(tag(" "), is_not("\n"));
 
@@ -232,27 +1424,75 @@ mod history {
         map(full_line, |(name, arg)| Line::Command { name, arg })(i)
     }
 
-    fn parse_dir(i: &str) -> IResult<&str, Line> {
+    fn parse_dir(i: &str) -> IResult<&str, Line<'_>> {
         let name = preceded(tag("dir "), is_not(" \n"));
         let full_line = terminated(name, tag("\n"));
 
         map(full_line, |name| Line::Dir { name })(i)
     }
 
-    fn parse_file(i: &str) -> IResult<&str, Line> {
+    fn parse_file(i: &str) -> IResult<&str, Line<'_>> {
         let size_str = terminated(is_a("1234567890"), tag(" "));
         let name = is_not("\n");
 
         let full_line = terminated(pair(size_str, name), tag("\n"));
 
-        map(full_line, |(size_str, name)| Line::File {
-            name,
-            size: size_str.parse().unwrap(),
-        })(i)
-    }
+        map(full_line, |(size_str, name)| Line::File {
+            name,
+            size: size_str.parse().unwrap(),
+        })(i)
+    }
+
+    pub fn parse_input(i: &str) -> IResult<&str, Vec<Line<'_>>> {
+        many1(alt((parse_file, parse_command, parse_dir)))(i)
+    }
+
+    /*
+        The other direction: a transcript that rebuilds `root` exactly.
+        Every directory is listed once, directories before files and each sorted by name,
+            then visited in the same order with a `cd` down and a `cd ..` back up.
+        Names containing spaces or newlines can not be read back, as with the real puzzle input.
+    */
+    pub fn write_transcript(root: &Dir) -> String {
+        let mut out = String::from("$ cd /\n");
+
+        //what is left to write, the next thing last.
+        enum Step<'a> {
+            List(&'a Dir),
+            Line(String),
+        }
+        let mut steps = vec![Step::List(root)];
+
+        while let Some(step) = steps.pop() {
+            let dir = match step {
+                Step::Line(line) => {
+                    out.push_str(&line);
+                    continue;
+                }
+                Step::List(dir) => dir,
+            };
+
+            let mut children: Vec<&Dir> = dir.children.values().collect();
+            children.sort_by(|a, b| a.name.cmp(&b.name));
+            let mut files: Vec<&crate::File> = dir.files.iter().collect();
+            files.sort_by(|a, b| a.name.cmp(&b.name));
+
+            out.push_str("$ ls\n");
+            for child in &children {
+                out.push_str(&format!("dir {}\n", child.name));
+            }
+            for f in files {
+                out.push_str(&format!("{} {}\n", f.size, f.name));
+            }
+
+            for child in children.into_iter().rev() {
+                steps.push(Step::Line("$ cd ..\n".to_string()));
+                steps.push(Step::List(child));
+                steps.push(Step::Line(format!("$ cd {}\n", child.name)));
+            }
+        }
 
-    pub fn parse_input(i: &str) -> IResult<&str, Vec<Line>> {
-        many1(alt((parse_file, parse_command, parse_dir)))(i)
+        out
     }
 
     #[cfg(test)]
@@ -301,11 +1541,321 @@ mod history {
     }
 }
 
+/*
+    JSON in and out, for looking at a tree with other tools or feeding in big made up trees.
+
+    The tree is a flat list of directories, the root first and every directory after its parent:
+        `[{"name": "/", "size": .., "files": [{"name": .., "size": ..}]}, {"name": .., "parent": 0, ..}]`
+        where `parent` is the parent's place in the list. Nothing is nested, so any depth loads.
+    `size` on a directory is the computed total and is written on export. It can be left out
+        when importing; if it is there it has to agree with the files below it.
+*/
+mod json {
+    use crate::report::Usage;
+    use crate::{Arena, Dir};
+    use serde::{Deserialize, Serialize};
+    use std::fmt;
+
+    #[derive(Serialize, Deserialize)]
+    struct JsonFile {
+        name: String,
+        size: usize,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct JsonDir {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        size: Option<usize>,
+        #[serde(default)]
+        files: Vec<JsonFile>,
+    }
+
+    #[derive(Debug)]
+    pub enum JsonError {
+        Syntax(serde_json::Error),
+        Empty,
+        Parent {
+            index: usize,
+        },
+        Duplicate {
+            path: String,
+        },
+        WrongSize {
+            path: String,
+            stated: usize,
+            actual: usize,
+        },
+    }
+
+    impl fmt::Display for JsonError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            match self {
+                JsonError::Syntax(e) => write!(f, "bad json: {}", e),
+                JsonError::Empty => write!(f, "no directories, not even the root"),
+                JsonError::Parent { index: 0 } => write!(f, "the root can not have a parent"),
+                JsonError::Parent { index } => {
+                    write!(f, "directory {} needs a parent listed before it", index)
+                }
+                JsonError::Duplicate { path } => write!(f, "{} appears more than once", path),
+                JsonError::WrongSize {
+                    path,
+                    stated,
+                    actual,
+                } => write!(
+                    f,
+                    "{} says it is {} bytes but its contents add up to {}",
+                    path, stated, actual
+                ),
+            }
+        }
+    }
+
+    //every directory in preorder, children and files by name, so the same tree always gives the same JSON.
+    fn export(usage: &Usage) -> Vec<JsonDir> {
+        let mut dirs = Vec::new();
+        let mut stack = vec![(usage, None)];
+
+        while let Some((usage, parent)) = stack.pop() {
+            let mut files: Vec<JsonFile> = usage
+                .dir
+                .files
+                .iter()
+                .map(|f| JsonFile {
+                    name: f.name.clone(),
+                    size: f.size,
+                })
+                .collect();
+            files.sort_by(|a, b| a.name.cmp(&b.name));
+
+            let i = dirs.len();
+            dirs.push(JsonDir {
+                name: usage.dir.name.clone(),
+                parent,
+                size: Some(usage.size),
+                files,
+            });
+            stack.extend(usage.children.iter().rev().map(|c| (c, Some(i))));
+        }
+
+        dirs
+    }
+
+    pub fn to_json(usage: &Usage) -> String {
+        let mut out = serde_json::to_string_pretty(&export(usage)).unwrap();
+        out.push('\n');
+        out
+    }
+
+    //the directories go into an Arena in list order, so a directory's place in the list is its place in the arena.
+    fn import(json: Vec<JsonDir>, arena: &mut Arena) -> Result<(), JsonError> {
+        let mut stated = Vec::with_capacity(json.len());
+
+        for (i, dir) in json.into_iter().enumerate() {
+            match (i, dir.parent) {
+                (0, None) => {}
+                (0, Some(_)) => return Err(JsonError::Parent { index: 0 }),
+                (_, Some(parent)) if parent < i => {
+                    if arena.children[parent].contains_key(&dir.name) {
+                        return Err(JsonError::Duplicate {
+                            path: arena.path_to(parent, &dir.name),
+                        });
+                    }
+                    arena.child(parent, &dir.name);
+                }
+                _ => return Err(JsonError::Parent { index: i }),
+            }
+
+            for f in dir.files {
+                if arena.files[i].contains_key(&f.name) {
+                    return Err(JsonError::Duplicate {
+                        path: arena.path_to(i, &f.name),
+                    });
+                }
+                arena.add_file(i, &f.name, f.size);
+            }
+            stated.push(dir.size);
+        }
+
+        //every directory comes after its parent, so going backwards adds each one in before its parent is checked.
+        let mut sizes: Vec<usize> = arena
+            .dirs
+            .iter()
+            .map(|d| d.files.iter().map(|f| f.size).sum())
+            .collect();
+        for i in (0..sizes.len()).rev() {
+            if let Some(stated) = stated[i] {
+                if stated != sizes[i] {
+                    return Err(JsonError::WrongSize {
+                        path: arena.path(i),
+                        stated,
+                        actual: sizes[i],
+                    });
+                }
+            }
+            if i > 0 {
+                sizes[arena.parents[i]] += sizes[i];
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn from_json(input: &str) -> Result<Dir, JsonError> {
+        let json: Vec<JsonDir> = serde_json::from_str(input).map_err(JsonError::Syntax)?;
+        let root_name = json.first().ok_or(JsonError::Empty)?.name.clone();
+
+        let mut root = Dir::new(&root_name);
+        let mut arena = Arena::take(&mut root);
+        let result = import(json, &mut arena);
+        arena.finish(&mut root);
+
+        result.map(|_| root)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use crate::json::*;
+        use crate::test_fixtures::*;
+        use crate::*;
+
+        #[test]
+        fn test_round_trip() {
+            let root = provided_root();
+            let json = to_json(&Usage::new(&root));
+
+            assert!(json.contains("\"size\": 48381165"));
+            assert!(json.contains("\"size\": 584"));
+
+            let loaded = from_json(&json).unwrap();
+            //files come back in name order, so compare the canonical transcripts.
+            assert_eq!(
+                history::write_transcript(&loaded),
+                history::write_transcript(&root)
+            );
+            assert_eq!(to_json(&Usage::new(&loaded)), json);
+        }
+
+        #[test]
+        fn test_sizes_are_optional() {
+            let root = from_json(
+                r#"[{"name": "/", "files": [{"name": "a", "size": 5}]},
+                    {"name": "d", "parent": 0, "files": [{"name": "b", "size": 7}]}]"#,
+            )
+            .unwrap();
+
+            assert_eq!(root.size(), 12);
+            assert_eq!(root.children["d"].files[0].size, 7);
+        }
+
+        #[test]
+        fn test_bad_json() {
+            assert!(matches!(
+                from_json(r#"[{"name": "/", "files": [{"name": "a"}]}]"#),
+                Err(JsonError::Syntax(_))
+            ));
+            assert!(matches!(from_json("[]"), Err(JsonError::Empty)));
+
+            match from_json(
+                r#"[{"name": "/"}, {"name": "d", "parent": 0, "size": 3, "files": [{"name": "b", "size": 7}]}]"#,
+            ) {
+                Err(e) => assert_eq!(
+                    e.to_string(),
+                    "/d says it is 3 bytes but its contents add up to 7"
+                ),
+                Ok(_) => panic!("wrong size should not load"),
+            }
+        }
+
+        #[test]
+        fn test_duplicates() {
+            assert!(matches!(
+                from_json(r#"[{"name": "/"}, {"name": "d", "parent": 0}, {"name": "d", "parent": 0}]"#),
+                Err(JsonError::Duplicate { path }) if path == "/d"
+            ));
+            assert!(matches!(
+                from_json(r#"[{"name": "/"}, {"name": "d", "parent": 0, "files": [{"name": "f", "size": 1}, {"name": "f", "size": 2}]}]"#),
+                Err(JsonError::Duplicate { path }) if path == "/d/f"
+            ));
+
+            //a file and a directory can share a name, as they can in a transcript.
+            let root = from_json(
+                r#"[{"name": "/", "files": [{"name": "d", "size": 1}]}, {"name": "d", "parent": 0}]"#,
+            )
+            .unwrap();
+            assert_eq!(root.size(), 1);
+        }
+
+        #[test]
+        fn test_parents() {
+            for (json, message) in [
+                (
+                    r#"[{"name": "/", "parent": 0}]"#,
+                    "the root can not have a parent",
+                ),
+                (
+                    r#"[{"name": "/"}, {"name": "d"}]"#,
+                    "directory 1 needs a parent listed before it",
+                ),
+                (
+                    r#"[{"name": "/"}, {"name": "d", "parent": 1}]"#,
+                    "directory 1 needs a parent listed before it",
+                ),
+            ] {
+                assert_eq!(from_json(json).unwrap_err().to_string(), message);
+            }
+        }
+
+        //a chain far deeper than serde_json would nest, one directory after another.
+        #[test]
+        fn test_deep_import() {
+            const DEPTH: usize = 100_000;
+
+            let mut json = String::from(r#"[{"name": "/"}"#);
+            for i in 0..DEPTH {
+                json.push_str(&format!(
+                    r#", {{"name": "d", "parent": {}, "files": [{{"name": "f", "size": 1}}]}}"#,
+                    i
+                ));
+            }
+            json.push(']');
+
+            let root = from_json(&json).unwrap();
+            assert_eq!(root.size(), DEPTH);
+            assert_eq!(root.dir_sizes().len(), DEPTH + 1);
+        }
+    }
+}
+
+/* the provided example and ways of building trees from transcripts, shared by the tests below and in the modules above. */
 #[cfg(test)]
-mod test_parsing {
-    const PROVIDED_INPUT: &str = include_str!("../7.test");
+mod test_fixtures {
+    use crate::*;
 
+    pub const PROVIDED_INPUT: &str = include_str!("../7.test");
+
+    //the tree a transcript builds, or the error that stopped it.
+    pub fn try_build_str(input: &str) -> Result<Dir, BuildError> {
+        let mut root = Dir::new("/");
+        build(history::parse_input(input).unwrap().1, &mut root)?;
+        Ok(root)
+    }
+
+    pub fn build_str(input: &str) -> Dir {
+        try_build_str(input).unwrap()
+    }
+
+    pub fn provided_root() -> Dir {
+        build_str(PROVIDED_INPUT)
+    }
+}
+
+#[cfg(test)]
+mod test_parsing {
     use crate::history;
+    use crate::test_fixtures::*;
     use crate::*;
 
     #[test]
@@ -334,10 +1884,7 @@ dir d
 
         let mut root = Dir::new("/");
 
-        build(
-            &mut history::parse_input(input).unwrap().1.into_iter(),
-            &mut root,
-        );
+        build(history::parse_input(input).unwrap().1, &mut root).unwrap();
 
         let mut children = HashMap::new();
         children.insert("a".to_string(), Dir::new("a"));
@@ -377,10 +1924,7 @@ $ ls
 
         let mut root = Dir::new("/");
 
-        build(
-            &mut history::parse_input(input).unwrap().1.into_iter(),
-            &mut root,
-        );
+        build(history::parse_input(input).unwrap().1, &mut root).unwrap();
 
         let mut children = HashMap::new();
         let mut a = Dir::new("a");
@@ -414,10 +1958,7 @@ $ ls
     fn test_provided_input() {
         let mut root = Dir::new("/");
 
-        build(
-            &mut history::parse_input(PROVIDED_INPUT).unwrap().1.into_iter(),
-            &mut root,
-        );
+        build(history::parse_input(PROVIDED_INPUT).unwrap().1, &mut root).unwrap();
 
         let mut verification_root = Dir::new("/");
         verification_root.files.push(File {
@@ -480,39 +2021,465 @@ $ ls
 
     #[test]
     fn test_size_provided_input() {
-        let mut root = Dir::new("/");
-
-        build(
-            &mut history::parse_input(PROVIDED_INPUT).unwrap().1.into_iter(),
-            &mut root,
-        );
+        let root = provided_root();
 
         assert_eq!(root.size(), 48381165);
     }
 
     #[test]
     fn test_aoc_size_thing() {
-        let mut root = Dir::new("/");
-
-        build(
-            &mut history::parse_input(PROVIDED_INPUT).unwrap().1.into_iter(),
-            &mut root,
-        );
+        let root = provided_root();
 
-        let (_, output) = root.aoc_dir_sum();
+        let (_, output) = root.aoc_dir_sum(AOC_DIR_SUM_MAX);
         assert_eq!(output, 95437);
     }
 
     #[test]
     fn test_aoc_min_delete() {
+        let root = provided_root();
+
+        let disk = Disk {
+            capacity: DISK_CAPACITY,
+            required_free: REQUIRED_FREE,
+        };
+        let output = aoc_min_delete(&root, disk);
+        assert_eq!(output, 24933642);
+
+        let (_, output) = root.aoc_dir_sum(1000);
+        assert_eq!(output, 584);
+    }
+
+    #[test]
+    fn test_change_dir() {
         let mut root = Dir::new("/");
+        let mut arena = Arena::take(&mut root);
+        let path = |arena: &Arena, mut dir: usize| {
+            let mut names = vec![];
+            while dir != 0 {
+                names.insert(0, arena.dirs[dir].name.clone());
+                dir = arena.parents[dir];
+            }
+            names
+        };
+
+        let cwd = arena.change_dir(0, "a/b");
+        assert_eq!(path(&arena, cwd), vec!["a", "b"]);
+        let cwd = arena.change_dir(cwd, "../c/./d");
+        assert_eq!(path(&arena, cwd), vec!["a", "c", "d"]);
+        let cwd = arena.change_dir(cwd, "/e");
+        assert_eq!(path(&arena, cwd), vec!["e"]);
+        let cwd = arena.change_dir(cwd, "../../..");
+        assert_eq!(cwd, 0);
+
+        arena.finish(&mut root);
+        assert_eq!(root.children.len(), 2);
+        assert_eq!(root.children["a"].children.len(), 2);
+    }
+
+    #[test]
+    fn test_cd_root_and_paths_anywhere() {
+        let root = try_build_str(
+            "$ cd a
+$ ls
+dir b
+1 x
+$ cd b
+$ ls
+2 y
+$ cd /
+$ cd a/b/c
+$ ls
+3 z
+$ cd /a
+$ ls
+4 w
+",
+        )
+        .unwrap();
+
+        let a = &root.children["a"];
+        assert_eq!(a.files.len(), 2);
+        assert_eq!(a.children["b"].files[0].name, "y");
+        assert_eq!(a.children["b"].children["c"].files[0].size, 3);
+        assert_eq!(root.size(), 1 + 2 + 3 + 4);
+    }
+
+    #[test]
+    fn test_repeated_ls_does_not_duplicate() {
+        let root = try_build_str(
+            "$ cd /
+$ ls
+dir a
+10 b.txt
+$ cd a
+$ ls
+5 c.txt
+$ cd ..
+$ ls
+dir a
+10 b.txt
+",
+        )
+        .unwrap();
+
+        assert_eq!(root.files.len(), 1);
+        assert_eq!(root.children["a"].files.len(), 1);
+        assert_eq!(root.size(), 15);
+    }
+
+    #[test]
+    fn test_unknown_command_is_an_error() {
+        assert_eq!(
+            try_build_str("$ cd /\n$ ls\n$ rm -rf a\n"),
+            Err(BuildError::UnknownCommand {
+                line: 3,
+                name: "rm".to_string(),
+                arg: Some("-rf a".to_string())
+            })
+        );
+
+        let error = try_build_str("$ cd\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 1: unsupported command cd");
+    }
+}
+
+#[cfg(test)]
+mod test_queries {
+    use crate::test_fixtures::*;
+    use crate::*;
+    use proptest::prelude::*;
+
+    fn paths<T>(found: Vec<(String, T)>) -> Vec<String> {
+        found.into_iter().map(|(path, _)| path).collect()
+    }
+
+    #[test]
+    fn test_find() {
+        let root = provided_root();
+
+        assert_eq!(root.find("/"), Some(Node::Dir(&root)));
+        assert_eq!(
+            root.find("a/e"),
+            Some(Node::Dir(&root.children["a"].children["e"]))
+        );
+        assert_eq!(
+            root.find("/a/e/i"),
+            Some(Node::File(&File {
+                name: "i".to_string(),
+                size: 584
+            }))
+        );
+        assert_eq!(root.find("a/nope"), None);
+        assert_eq!(root.find("b.txt/x"), None);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("**/*.txt", "/b.txt"));
+        assert!(glob_match("**/*.txt", "/x/y/b.txt"));
+        assert!(!glob_match("**/*.txt", "/x/b.txt.gz"));
+        assert!(glob_match("/a/*", "/a/f"));
+        assert!(!glob_match("/a/*", "/a/e/i"));
+        assert!(glob_match("a/**", "/a/e/i"));
+        assert!(glob_match("?.*", "/d.log"));
+        assert!(!glob_match("?.*", "/ab.lst"));
+    }
+
+    //the matcher spelled out directly: try every way each star could go.
+    fn naive_match(pattern: &str, path: &str) -> bool {
+        fn segments(pattern: &[&str], path: &[&str]) -> bool {
+            match (pattern.first(), path.first()) {
+                (None, None) => true,
+                (Some(&"**"), _) => {
+                    segments(&pattern[1..], path)
+                        || (!path.is_empty() && segments(pattern, &path[1..]))
+                }
+                (Some(p), Some(s)) => {
+                    one_name(p.as_bytes(), s.as_bytes()) && segments(&pattern[1..], &path[1..])
+                }
+                _ => false,
+            }
+        }
+
+        fn one_name(pattern: &[u8], name: &[u8]) -> bool {
+            match (pattern.first(), name.first()) {
+                (None, None) => true,
+                (Some(b'*'), _) => {
+                    one_name(&pattern[1..], name)
+                        || (!name.is_empty() && one_name(pattern, &name[1..]))
+                }
+                (Some(b'?'), Some(_)) => one_name(&pattern[1..], &name[1..]),
+                (Some(p), Some(c)) => p == c && one_name(&pattern[1..], &name[1..]),
+                _ => false,
+            }
+        }
+
+        let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+        let path: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        segments(&pattern, &path)
+    }
+
+    proptest! {
+        #[test]
+        fn test_glob_match_agrees_with_naive(
+            pattern in "(/(\\*\\*|[ab?*]{0,4})){0,5}",
+            path in "(/[ab]{1,4}){0,6}",
+        ) {
+            prop_assert_eq!(glob_match(&pattern, &path), naive_match(&pattern, &path));
+        }
+    }
+
+    #[test]
+    fn test_glob() {
+        let root = provided_root();
+
+        assert_eq!(
+            paths(root.glob("**/*.*")),
+            vec!["/a/h.lst", "/b.txt", "/c.dat", "/d/d.ext", "/d/d.log"]
+        );
+        assert_eq!(paths(root.glob("*")), vec!["/a", "/b.txt", "/c.dat", "/d"]);
+        assert_eq!(paths(root.glob("**/e/**")), vec!["/a/e", "/a/e/i"]);
+    }
+
+    #[test]
+    fn test_files() {
+        let root = provided_root();
+
+        assert_eq!(root.files_with_paths().len(), 10);
+        assert_eq!(paths(root.files_sized(..1000)), vec!["/a/e/i"]);
+        assert_eq!(
+            paths(root.files_sized(8000000..=8504156)),
+            vec!["/c.dat", "/d/d.log"]
+        );
+        assert_eq!(
+            paths(root.children["a"].files_with_paths()),
+            vec!["/e/i", "/f", "/g", "/h.lst"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_transcript {
+    use crate::test_fixtures::*;
+    use crate::*;
+    use proptest::prelude::*;
+
+    //a tree from a list of directory paths, each maybe holding a file there.
+    //a small name alphabet makes a file and a directory share a name now and then.
+    fn tree() -> impl Strategy<Value = Dir> {
+        let path = prop::collection::vec("[a-f]", 0..5);
+        let file = prop::option::of(("[a-f](\\.[0-2])?", 0usize..300000));
+
+        prop::collection::vec((path, file), 0..30).prop_map(|entries| {
+            let mut root = Dir::new("/");
+            for (path, file) in entries {
+                let mut dir = &mut root;
+                for name in path {
+                    dir = dir
+                        .children
+                        .entry(name.clone())
+                        .or_insert_with(|| Dir::new(&name));
+                }
+                if let Some((name, size)) = file {
+                    match dir.files.iter_mut().find(|f| f.name == name) {
+                        Some(existing) => existing.size = size,
+                        None => dir.files.push(File { name, size }),
+                    }
+                }
+            }
+
+            //a rebuilt tree has its files in listing order, which is sorted.
+            let mut stack = vec![&mut root];
+            while let Some(dir) = stack.pop() {
+                dir.files.sort_by(|a, b| a.name.cmp(&b.name));
+                stack.extend(dir.children.values_mut());
+            }
+            root
+        })
+    }
+
+    #[test]
+    fn test_write_provided_input() {
+        let written = history::write_transcript(&provided_root());
+
+        assert_eq!(
+            written,
+            "$ cd /\n$ ls\ndir a\ndir d\n14848514 b.txt\n8504156 c.dat\n\
+             $ cd a\n$ ls\ndir e\n29116 f\n2557 g\n62596 h.lst\n\
+             $ cd e\n$ ls\n584 i\n$ cd ..\n$ cd ..\n\
+             $ cd d\n$ ls\n5626152 d.ext\n8033020 d.log\n4060174 j\n7214296 k\n$ cd ..\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let written = history::write_transcript(&Dir::new("/"));
+        assert_eq!(written, "$ cd /\n$ ls\n");
+        assert_eq!(build_str(&written), Dir::new("/"));
+    }
+
+    #[test]
+    fn test_messy_transcript_is_normalized() {
+        let messy = "$ cd /a/b\n$ ls\n10 x\n$ cd /\n$ ls\n5 y\ndir a\n$ cd a\n$ ls\n$ ls\ndir b\n";
+        assert_eq!(
+            history::write_transcript(&build_str(messy)),
+            "$ cd /\n$ ls\ndir a\n5 y\n$ cd a\n$ ls\ndir b\n$ cd b\n$ ls\n10 x\n$ cd ..\n$ cd ..\n"
+        );
+    }
+
+    proptest! {
+        //parse -> build -> write -> parse gives back the same lines, and the same tree.
+        #[test]
+        fn test_round_trip(tree in tree()) {
+            let written = history::write_transcript(&tree);
+
+            let (rest, lines) = history::parse_input(&written).unwrap();
+            prop_assert_eq!(rest, "");
+
+            let mut rebuilt = Dir::new("/");
+            build(lines, &mut rebuilt).unwrap();
+            prop_assert_eq!(&rebuilt, &tree);
+
+            let rewritten = history::write_transcript(&rebuilt);
+            prop_assert_eq!(&rewritten, &written);
+            prop_assert_eq!(
+                history::parse_input(&rewritten).unwrap().1,
+                history::parse_input(&written).unwrap().1
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_deep {
+    use crate::*;
+
+    const DEPTH: usize = 100_000;
+
+    //a chain of depth directories all called d, each holding one byte, walked down and back up again.
+    // without the last listing it is exactly what write_transcript gives back for the chain.
+    fn deep_transcript(depth: usize) -> String {
+        let mut transcript = String::from("$ cd /\n");
+        for _ in 0..depth {
+            transcript.push_str("$ ls\ndir d\n1 f\n$ cd d\n");
+        }
+        transcript.push_str("$ ls\n1 f\n");
+        transcript.push_str(&"$ cd ..\n".repeat(depth));
+        transcript.push_str("$ ls\ndir d\n1 f\n");
+        transcript
+    }
 
+    fn chain(depth: usize) -> Dir {
+        let mut root = Dir::new("/");
         build(
-            &mut history::parse_input(PROVIDED_INPUT).unwrap().1.into_iter(),
+            history::parse_input(&deep_transcript(depth)).unwrap().1,
             &mut root,
+        )
+        .unwrap();
+        root
+    }
+
+    #[test]
+    fn test_deep_compare() {
+        let tree = chain(DEPTH);
+        assert_eq!(tree, chain(DEPTH));
+        assert_ne!(tree, chain(DEPTH - 1));
+
+        let debug = format!("{:?}", tree);
+        assert!(debug.starts_with("[(0, \"/\", [File { size: 1, name: \"f\" }]), (1, \"d\", "));
+        assert_eq!(debug.matches("File").count(), DEPTH + 1);
+    }
+
+    #[test]
+    fn test_deep_transcript() {
+        let transcript = deep_transcript(DEPTH);
+        let (rest, lines) = history::parse_input(&transcript).unwrap();
+        assert_eq!(rest, "");
+
+        let mut root = Dir::new("/");
+        build(lines, &mut root).unwrap();
+
+        assert_eq!(root.size(), DEPTH + 1);
+        assert_eq!(root.dir_sizes().len(), DEPTH + 1);
+        // the ten deepest directories hold 1 to 10 bytes.
+        assert_eq!(root.aoc_dir_sum(10), (DEPTH + 1, 55));
+        assert_eq!(root.aoc_dir_size_min_above(DEPTH - 1), (DEPTH + 1, DEPTH));
+
+        //building onto the same tree again only revisits what is already there.
+        build(history::parse_input(&transcript).unwrap().1, &mut root).unwrap();
+        assert_eq!(root.size(), DEPTH + 1);
+    }
+
+    //one directory listing DEPTH files, listed again with new sizes, and again onto the built tree.
+    #[test]
+    fn test_wide_listing() {
+        let listing: String = (0..DEPTH).map(|i| format!("1 f{}\n", i)).collect();
+        let relisting: String = (0..DEPTH).map(|i| format!("2 f{}\n", i)).collect();
+        let transcript = format!("$ cd /\n$ ls\n{}$ ls\n{}", listing, relisting);
+
+        let mut root = Dir::new("/");
+        build(history::parse_input(&transcript).unwrap().1, &mut root).unwrap();
+        assert_eq!(root.files.len(), DEPTH);
+        assert_eq!(root.size(), 2 * DEPTH);
+
+        let transcript = format!("$ cd /\n$ ls\n{}", listing);
+        build(history::parse_input(&transcript).unwrap().1, &mut root).unwrap();
+        assert_eq!(root.files.len(), DEPTH);
+        assert_eq!(root.size(), DEPTH);
+    }
+
+    #[test]
+    fn test_deep_reports() {
+        let root = chain(DEPTH);
+        let usage = report::Usage::new(&root);
+        assert_eq!(usage.size, DEPTH + 1);
+
+        let transcript = deep_transcript(DEPTH);
+        let written = history::write_transcript(&root);
+        assert_eq!(
+            Some(written.as_str()),
+            transcript.strip_suffix("$ ls\ndir d\n1 f\n")
         );
 
-        let output = aoc_min_delete(&root);
-        assert_eq!(output, 24933642);
+        let json = json::to_json(&usage);
+        let loaded = json::from_json(&json).unwrap();
+        assert_eq!(history::write_transcript(&loaded), written);
+
+        //the one directory holding 10 bytes is 10 from the bottom.
+        let disk = Disk {
+            capacity: DEPTH + 1,
+            required_free: 10,
+        };
+        let plan = planner::plan_deletion(&usage, disk, planner::Minimize::Bytes).unwrap();
+        assert_eq!(plan.dirs.len(), 1);
+        assert_eq!(plan.dirs[0].0, "/d".repeat(DEPTH - 9));
+        assert_eq!(plan.freed, 10);
+    }
+
+    /*
+        Anything listing every path is quadratic on a chain, so these walk a shorter one,
+            on a stack small enough that recursing once per directory overflowed it.
+    */
+    #[test]
+    fn test_deep_paths() {
+        const SHORTER: usize = 3000;
+
+        std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(|| {
+                let root = chain(SHORTER);
+                assert_eq!(root.entries().len(), 2 * SHORTER + 1);
+                assert_eq!(root.glob("**/d/f").len(), SHORTER);
+                assert_eq!(root.files_sized(..=1).len(), SHORTER + 1);
+
+                let usage = report::Usage::new(&root);
+                assert_eq!(usage.dirs().len(), SHORTER + 1);
+                assert_eq!(report::tree(&usage).lines().count(), 2 * SHORTER + 2);
+                assert_eq!(report::du(&usage).lines().count(), SHORTER + 1);
+                assert_eq!(report::largest_files(&usage, 5).lines().count(), 5);
+            })
+            .unwrap()
+            .join()
+            .unwrap();
     }
 }
@@ -1,47 +1,242 @@
-use std::collections::HashSet;
-use std::fs;
+#![allow(clippy::needless_return)]
+
+use std::fs::File;
+use std::io::{self, BufReader, Read};
 
 //find the start of a particular message or packet by scanning
 //     for a special header value and then returning the position
 //      after that special value exists.
 
-fn find_start(s: &str, window_size: usize) -> usize {
-    let offset = s
-        .chars()
-        .collect::<Vec<char>>()
-        .windows(window_size)
-        .enumerate()
-        .find(|(_, stuff)| {
-            stuff.iter().collect::<HashSet<_>>().len() == window_size
-        })
-        .map(|(i, _)| i)
-        .unwrap();
-    println!("{} {}", s, offset);
-    return offset + window_size;
+/*
+    Keeps a count of every byte in the current window, plus how many of those counts are non-zero.
+    Each byte that enters or leaves the window only touches its own count, so a scan is O(n)
+    no matter how wide the window is.
+
+    Works on bytes rather than chars, which is the same thing for the ASCII puzzle input.
+*/
+struct MarkerDetector {
+    window_size: usize,
+    //the last window_size bytes seen, written round-robin.
+    window: Vec<u8>,
+    counts: [usize; 256],
+    distinct: usize,
+    seen: usize,
+}
+
+impl MarkerDetector {
+    fn new(window_size: usize) -> MarkerDetector {
+        MarkerDetector {
+            window_size,
+            window: vec![0; window_size],
+            counts: [0; 256],
+            distinct: 0,
+            seen: 0,
+        }
+    }
+
+    //add the next byte. returns true if the last window_size bytes are all different.
+    fn push(&mut self, b: u8) -> bool {
+        if self.window_size == 0 {
+            return false;
+        }
+
+        let slot = self.seen % self.window_size;
+
+        if self.seen >= self.window_size {
+            let leaving = self.window[slot] as usize;
+            self.counts[leaving] -= 1;
+            if self.counts[leaving] == 0 {
+                self.distinct -= 1;
+            }
+        }
+
+        self.window[slot] = b;
+        self.counts[b as usize] += 1;
+        if self.counts[b as usize] == 1 {
+            self.distinct += 1;
+        }
+
+        self.seen += 1;
+        return self.distinct == self.window_size;
+    }
+}
+
+//the number of bytes read up to and including the first marker, or None if there is no marker.
+fn find_start(input: impl Read, window_size: usize) -> io::Result<Option<usize>> {
+    let mut detector = MarkerDetector::new(window_size);
+
+    for b in BufReader::new(input).bytes() {
+        if detector.push(b?) {
+            return Ok(Some(detector.seen));
+        }
+    }
+
+    return Ok(None);
+}
+
+//every position where a marker ends, for each of the window sizes, from one pass over the input.
+// the result lines up with window_sizes; a size with no markers gets an empty list.
+fn find_all_markers(input: impl Read, window_sizes: &[usize]) -> io::Result<Vec<Vec<usize>>> {
+    let mut detectors: Vec<MarkerDetector> = window_sizes
+        .iter()
+        .map(|size| MarkerDetector::new(*size))
+        .collect();
+    let mut markers = vec![Vec::new(); window_sizes.len()];
+
+    for b in BufReader::new(input).bytes() {
+        let b = b?;
+        for (detector, found) in detectors.iter_mut().zip(markers.iter_mut()) {
+            if detector.push(b) {
+                found.push(detector.seen);
+            }
+        }
+    }
+
+    return Ok(markers);
+}
+
+fn find_packet_start(input: impl Read) -> io::Result<Option<usize>> {
+    return find_start(input, 4);
 }
 
-fn find_packet_start(s: &str) -> usize {
-    return find_start(s, 4);
+fn find_message_start(input: impl Read) -> io::Result<Option<usize>> {
+    return find_start(input, 14);
 }
 
-fn find_message_start(s: &str) -> usize {
-    return find_start(s, 14);
+fn main() -> io::Result<()> {
+    println!(
+        "Find Packets: {:?}",
+        find_packet_start(File::open("./6.input")?)?
+    );
+    println!(
+        "Find Messages: {:?}",
+        find_message_start(File::open("./6.input")?)?
+    );
+
+    //pass window sizes to list every marker position for each of them.
+    let window_sizes: Vec<usize> = std::env::args()
+        .skip(1)
+        .map(|a| a.parse().expect("window sizes should be numbers"))
+        .collect();
+
+    let markers = find_all_markers(File::open("./6.input")?, &window_sizes)?;
+    for (size, found) in window_sizes.iter().zip(markers) {
+        println!("{} markers of size {}: {:?}", found.len(), size, found);
+    }
+
+    Ok(())
 }
 
-fn main() {
-    let input = fs::read_to_string("./6.input").expect("Error while reading");
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
+
+    //the original approach: build a set for every window. slow, but obviously right.
+    fn naive_markers(s: &str, window_size: usize) -> Vec<usize> {
+        if window_size == 0 {
+            return vec![];
+        }
+
+        s.chars()
+            .collect::<Vec<char>>()
+            .windows(window_size)
+            .enumerate()
+            .filter(|(_, stuff)| stuff.iter().collect::<HashSet<_>>().len() == window_size)
+            .map(|(i, _)| i + window_size)
+            .collect()
+    }
+
+    #[test]
+    fn test_packet_examples() {
+        assert_eq!(
+            find_packet_start("bvwbjplbgvbhsrlpgdmjqwftvncz".as_bytes()).unwrap(),
+            Some(5)
+        );
+        assert_eq!(
+            find_packet_start("nppdvjthqldpwncqszvftbrmjlhg".as_bytes()).unwrap(),
+            Some(6)
+        );
+        assert_eq!(
+            find_packet_start("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".as_bytes()).unwrap(),
+            Some(10)
+        );
+        assert_eq!(
+            find_packet_start("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".as_bytes()).unwrap(),
+            Some(11)
+        );
+    }
+
+    #[test]
+    fn test_message_examples() {
+        assert_eq!(
+            find_message_start("mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes()).unwrap(),
+            Some(19)
+        );
+        assert_eq!(
+            find_message_start("bvwbjplbgvbhsrlpgdmjqwftvncz".as_bytes()).unwrap(),
+            Some(23)
+        );
+        assert_eq!(
+            find_message_start("nppdvjthqldpwncqszvftbrmjlhg".as_bytes()).unwrap(),
+            Some(23)
+        );
+        assert_eq!(
+            find_message_start("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg".as_bytes()).unwrap(),
+            Some(29)
+        );
+        assert_eq!(
+            find_message_start("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw".as_bytes()).unwrap(),
+            Some(26)
+        );
+    }
+
+    #[test]
+    fn test_find_all_markers() {
+        let markers = find_all_markers("abcabcc".as_bytes(), &[2, 3, 4]).unwrap();
+        assert_eq!(markers, vec![vec![2, 3, 4, 5, 6], vec![3, 4, 5, 6], vec![]]);
+
+        assert_eq!(find_start("aaaa".as_bytes(), 2).unwrap(), None);
+        assert_eq!(find_packet_start("abcabc".as_bytes()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_short_input() {
+        assert_eq!(find_packet_start("".as_bytes()).unwrap(), None);
+        assert_eq!(find_packet_start("abc".as_bytes()).unwrap(), None);
+        assert_eq!(find_packet_start("abcd".as_bytes()).unwrap(), Some(4));
+        assert_eq!(find_start("a".as_bytes(), 1).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn test_window_larger_than_input() {
+        assert_eq!(
+            find_message_start("abcdefghijklm".as_bytes()).unwrap(),
+            None
+        );
+        assert_eq!(
+            find_all_markers("abc".as_bytes(), &[5, 300]).unwrap(),
+            vec![Vec::<usize>::new(), vec![]]
+        );
+    }
 
-    assert_eq!(find_packet_start("bvwbjplbgvbhsrlpgdmjqwftvncz"), 5);
-    assert_eq!(find_packet_start("nppdvjthqldpwncqszvftbrmjlhg"), 6);
-    assert_eq!(find_packet_start("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 10);
-    assert_eq!(find_packet_start("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 11);
-    println!("Find Packets: {}", find_packet_start(&input));
+    #[test]
+    fn test_window_of_zero_never_matches() {
+        assert_eq!(find_start("abc".as_bytes(), 0).unwrap(), None);
+    }
 
-    assert_eq!(find_message_start("mjqjpqmgbljsphdztnvjfqwrcgsmlb"), 19);
-    assert_eq!(find_message_start("bvwbjplbgvbhsrlpgdmjqwftvncz"), 23);
-    assert_eq!(find_message_start("nppdvjthqldpwncqszvftbrmjlhg"), 23);
-    assert_eq!(find_message_start("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 29);
-    assert_eq!(find_message_start("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 26);
+    proptest! {
+        //small alphabets so repeats, and windows with no marker, are common.
+        #[test]
+        fn test_matches_naive(s in "[a-e]{0,40}|[a-t]{0,80}", window_sizes in prop::collection::vec(0usize..30, 1..5)) {
+            let markers = find_all_markers(s.as_bytes(), &window_sizes).unwrap();
 
-    println!("Find Messages: {}", find_message_start(&input));
+            for (size, found) in window_sizes.iter().zip(markers) {
+                let expected = naive_markers(&s, *size);
+                prop_assert_eq!(&found, &expected, "window {}", size);
+                prop_assert_eq!(find_start(s.as_bytes(), *size).unwrap(), expected.first().copied());
+            }
+        }
+    }
 }
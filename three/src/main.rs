@@ -16,65 +16,501 @@ To help prioritize item rearrangement, every item type can be converted to a pri
 Lowercase item types a through z have priorities 1 through 26.
 Uppercase item types A through Z have priorities 27 through 52.
 
+Part 2: the elves are divided into groups of three, and the badge is the only item type carried by all
+three elves in a group.
+
+Neither "two" nor "three" is special here: a rucksack can be split into any number of equal compartments
+and groups can be any size, and a rucksack or group that shares zero or several item types is reported
+rather than trusted.
 */
+#![allow(clippy::needless_return)]
+
 use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::str::FromStr;
+use std::io::{self, Write};
+
+const NUM_COMPARTMENTS: usize = 2;
+const GROUP_SIZE: usize = 3;
+
+fn priority(c: char) -> i32 {
+    return if c.is_lowercase() {
+        c as i32 - 'a' as i32 + 1
+    } else {
+        c as i32 - 'A' as i32 + 27
+    };
+}
+
+//the item types that appear in every one of the lists, sorted so the output is stable.
+fn common_items(lists: &[&str]) -> Vec<char> {
+    let Some((first, rest)) = lists.split_first() else {
+        return vec![];
+    };
+
+    first
+        .chars()
+        .unique()
+        .filter(|c| rest.iter().all(|list| list.contains(*c)))
+        .sorted()
+        .collect()
+}
 
 #[derive(Debug)]
 struct Rucksack {
-    left: String,
-    right: String,
+    compartments: Vec<String>,
+}
+
+impl Rucksack {
+    //split a line into num_compartments equally sized compartments.
+    fn parse(s: &str, num_compartments: usize) -> Result<Rucksack, String> {
+        //item types are ascii letters, and the split below counts bytes.
+        if !s.is_ascii() {
+            return Err(format!("{:?} has items that are not item types", s));
+        }
+        if num_compartments == 0 || !s.len().is_multiple_of(num_compartments) {
+            return Err(format!(
+                "{} items can not be split into {} equal compartments",
+                s.len(),
+                num_compartments
+            ));
+        }
+
+        let compartment_length = s.len() / num_compartments;
+        let compartments = (0..num_compartments)
+            .map(|i| s[i * compartment_length..(i + 1) * compartment_length].to_string())
+            .collect();
+
+        return Ok(Rucksack { compartments });
+    }
+
+    fn duplicated_types(&self) -> Vec<char> {
+        let compartments: Vec<&str> = self.compartments.iter().map(|c| c.as_str()).collect();
+        common_items(&compartments)
+    }
 }
 
-impl FromStr for Rucksack {
-    type Err = core::convert::Infallible;
+// a rucksack or group that did not share exactly one item type.
+#[derive(Debug, PartialEq)]
+struct Mismatch {
+    first_line: usize,
+    common: Vec<char>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EntryKind {
+    Rucksack,
+    Group,
+    //the leftover lines at the end when they do not make a whole group.
+    ShortGroup,
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let compartment_length = s.len() / 2;
-        return Ok(Rucksack {
-            left: s[..compartment_length].to_string(),
-            right: s[compartment_length..].to_string(),
-        });
+impl fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntryKind::Rucksack => write!(f, "rucksack"),
+            EntryKind::Group => write!(f, "group"),
+            EntryKind::ShortGroup => write!(f, "short group"),
+        }
     }
 }
 
-impl Rucksack {
-    fn duplicated_type_score(&self) -> i32 {
-        let mut total: i32 = 0;
-
-        println!("new rucksack");
-        let dupes: Vec<char> = self
-            .left
-            .chars()
-            .unique()
-            .chain(self.right.chars().unique())
-            .duplicates()
-            .dedup()
-            .collect();
+// one line of the audit: the item types shared by every compartment of a rucksack, or by every rucksack in a group.
+#[derive(Debug, PartialEq)]
+struct AuditEntry {
+    kind: EntryKind,
+    first_line: usize,
+    items: Vec<char>,
+}
+
+impl AuditEntry {
+    //only a whole rucksack or group sharing exactly one item type scores. everything else is a mismatch.
+    fn scoring_item(&self) -> Option<char> {
+        match self.items.as_slice() {
+            [c] if self.kind != EntryKind::ShortGroup => Some(*c),
+            _ => None,
+        }
+    }
+}
 
-        for c in dupes {
-            total += if c.is_lowercase() {
-                c as i32 - 'a' as i32 + 1
+fn rucksack_entries(input: &str, num_compartments: usize) -> Result<Vec<AuditEntry>, String> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let rucksack = Rucksack::parse(line, num_compartments)
+                .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            Ok(AuditEntry {
+                kind: EntryKind::Rucksack,
+                first_line: i + 1,
+                items: rucksack.duplicated_types(),
+            })
+        })
+        .collect()
+}
+
+fn group_entries(input: &str, group_size: usize) -> Result<Vec<AuditEntry>, String> {
+    check_group_size(group_size)?;
+
+    let lines: Vec<&str> = input.lines().collect();
+    let entries = lines
+        .chunks(group_size)
+        .enumerate()
+        .map(|(i, group)| AuditEntry {
+            kind: if group.len() < group_size {
+                EntryKind::ShortGroup
             } else {
-                c as i32 - 'A' as i32 + 27
+                EntryKind::Group
+            },
+            first_line: i * group_size + 1,
+            items: common_items(group),
+        })
+        .collect();
+
+    return Ok(entries);
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Tally {
+    total: i32,
+    mismatches: Vec<Mismatch>,
+}
+
+impl Tally {
+    fn from_entries(entries: Vec<AuditEntry>) -> Tally {
+        let mut tally = Tally::default();
+
+        for entry in entries {
+            if let Some(c) = entry.scoring_item() {
+                tally.total += priority(c);
+            } else {
+                tally.mismatches.push(Mismatch {
+                    first_line: entry.first_line,
+                    common: entry.items,
+                });
+            }
+        }
+
+        return tally;
+    }
+}
+
+//part 1: the item type found in every compartment of each rucksack.
+fn score_rucksacks(input: &str, num_compartments: usize) -> Result<Tally, String> {
+    return Ok(Tally::from_entries(rucksack_entries(
+        input,
+        num_compartments,
+    )?));
+}
+
+//part 2: the item type found in every rucksack of each group of group_size lines.
+// a trailing group with fewer than group_size lines is reported as a mismatch.
+fn score_groups(input: &str, group_size: usize) -> Result<Tally, String> {
+    return Ok(Tally::from_entries(group_entries(input, group_size)?));
+}
+
+fn check_group_size(group_size: usize) -> Result<(), String> {
+    if group_size == 0 {
+        return Err("groups need at least one rucksack".to_string());
+    }
+    return Ok(());
+}
+
+fn report(label: &str, tally: &Tally) {
+    for mismatch in &tally.mismatches {
+        println!(
+            "{}: starting at line {} shares {} item types {:?}",
+            label,
+            mismatch.first_line,
+            mismatch.common.len(),
+            mismatch.common
+        );
+    }
+    println!("{} total: {}", label, tally.total);
+}
+
+//every rucksack, then every group.
+fn audit(
+    input: &str,
+    num_compartments: usize,
+    group_size: usize,
+) -> Result<Vec<AuditEntry>, String> {
+    let mut entries = rucksack_entries(input, num_compartments)?;
+    entries.extend(group_entries(input, group_size)?);
+
+    return Ok(entries);
+}
+
+//how often each item type was misplaced in a rucksack, and how often it was a group's badge.
+// a group only has a badge when it scores, so short groups and groups sharing several types are left out.
+fn item_frequencies(entries: &[AuditEntry]) -> BTreeMap<i32, (char, usize, usize)> {
+    let mut frequencies = BTreeMap::new();
+
+    for entry in entries {
+        let items = match entry.kind {
+            EntryKind::Rucksack => entry.items.clone(),
+            EntryKind::Group | EntryKind::ShortGroup => entry.scoring_item().into_iter().collect(),
+        };
+
+        for c in items {
+            let counts = frequencies.entry(priority(c)).or_insert((c, 0, 0));
+            match entry.kind {
+                EntryKind::Rucksack => counts.1 += 1,
+                EntryKind::Group | EntryKind::ShortGroup => counts.2 += 1,
             }
         }
-        return total;
     }
+
+    return frequencies;
+}
+
+fn write_report(out: &mut impl Write, entries: &[AuditEntry]) -> io::Result<()> {
+    for entry in entries {
+        let items = entry
+            .items
+            .iter()
+            .map(|c| format!("{} ({})", c, priority(*c)))
+            .join(", ");
+        writeln!(
+            out,
+            "{} at line {}: [{}]",
+            entry.kind, entry.first_line, items
+        )?;
+    }
+
+    writeln!(out)?;
+    writeln!(out, "item priority rucksacks groups")?;
+    for (priority, (c, rucksacks, groups)) in item_frequencies(entries) {
+        writeln!(
+            out,
+            "{:>4} {:>8} {:>9} {:>6}",
+            c, priority, rucksacks, groups
+        )?;
+    }
+
+    Ok(())
+}
+
+//one row per shared item type. entries with nothing in common still get a row with empty item columns.
+fn write_csv(out: &mut impl Write, entries: &[AuditEntry]) -> io::Result<()> {
+    writeln!(out, "kind,first_line,item,priority")?;
+    for entry in entries {
+        if entry.items.is_empty() {
+            writeln!(out, "{},{},,", entry.kind, entry.first_line)?;
+        }
+        for c in &entry.items {
+            writeln!(
+                out,
+                "{},{},{},{}",
+                entry.kind,
+                entry.first_line,
+                c,
+                priority(*c)
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_frequency_csv(out: &mut impl Write, entries: &[AuditEntry]) -> io::Result<()> {
+    writeln!(out, "item,priority,rucksacks,groups")?;
+    for (priority, (c, rucksacks, groups)) in item_frequencies(entries) {
+        writeln!(out, "{},{},{},{}", c, priority, rucksacks, groups)?;
+    }
+
+    Ok(())
 }
 
-fn main() {
+/*
+    no arguments prints the two totals.
+    `report` lists every rucksack and group with what it has in common, then the frequency table.
+    `csv` and `frequency-csv` write the same two tables as CSV.
+*/
+fn main() -> io::Result<()> {
     let input = fs::read_to_string("./3.input").expect("Error while reading");
+    let mode = std::env::args().nth(1);
 
-    let total = input
-        .lines()
-        .map(|line| {
-            let rucksack = line.parse::<Rucksack>().unwrap();
-            println!("{:?}", rucksack);
-            return rucksack.duplicated_type_score();
-        })
-        .sum::<i32>();
+    if let Some(mode) = mode {
+        let entries =
+            audit(&input, NUM_COMPARTMENTS, GROUP_SIZE).expect("could not split rucksacks");
+        let mut out = io::stdout().lock();
+
+        return match mode.as_str() {
+            "report" => write_report(&mut out, &entries),
+            "csv" => write_csv(&mut out, &entries),
+            "frequency-csv" => write_frequency_csv(&mut out, &entries),
+            _ => panic!(
+                "unknown mode {:?}, expected report, csv or frequency-csv",
+                mode
+            ),
+        };
+    }
+
+    let rucksacks = score_rucksacks(&input, NUM_COMPARTMENTS).expect("could not split rucksacks");
+    report("rucksacks", &rucksacks);
+
+    let groups = score_groups(&input, GROUP_SIZE).expect("could not split groups");
+    report("groups", &groups);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    const PROVIDED_INPUT: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
+jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
+PmmdzqPrVvPwwTWBwg
+wMqvLMZHhHMvwLHjbvcjnnSBnvTQFn
+ttgJtRGJQctTZtZT
+CrZsJsPPZsGzwwsLwLmpwMDw
+";
+
+    #[test]
+    fn test_common_items() {
+        assert_eq!(common_items(&["abc", "bcd", "cbe"]), vec!['b', 'c']);
+        assert_eq!(common_items(&["abc", "def"]), vec![]);
+        assert_eq!(common_items(&[]), vec![]);
+    }
+
+    #[test]
+    fn test_split_compartments() {
+        let rucksack = Rucksack::parse("aabbcc", 3).unwrap();
+        assert_eq!(rucksack.compartments, vec!["aa", "bb", "cc"]);
+
+        assert!(Rucksack::parse("aabbc", 2).is_err());
+        assert!(Rucksack::parse("aabbc", 0).is_err());
+        assert!(Rucksack::parse("aé", 2).is_err());
+        assert!(score_rucksacks("ab\néa\n", 2)
+            .unwrap_err()
+            .starts_with("line 2"));
+
+        assert!(score_groups("ab\n", 0).is_err());
+        assert!(audit("ab\n", 2, 0).is_err());
+    }
+
+    #[test]
+    fn test_provided_input() {
+        assert_eq!(score_rucksacks(PROVIDED_INPUT, 2).unwrap().total, 157);
+        assert_eq!(score_groups(PROVIDED_INPUT, 3).unwrap().total, 70);
+    }
+
+    #[test]
+    fn test_mismatches_are_reported() {
+        let tally = score_groups("abc\nabd\nxyz\nab\n", 2).unwrap();
+        assert_eq!(tally.total, 0);
+        assert_eq!(
+            tally.mismatches,
+            vec![
+                Mismatch {
+                    first_line: 1,
+                    common: vec!['a', 'b']
+                },
+                Mismatch {
+                    first_line: 3,
+                    common: vec![]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audit() {
+        let entries = audit(PROVIDED_INPUT, 2, 3).unwrap();
+        assert_eq!(entries.len(), 8);
+        assert_eq!(
+            entries[0],
+            AuditEntry {
+                kind: EntryKind::Rucksack,
+                first_line: 1,
+                items: vec!['p']
+            }
+        );
+        assert_eq!(
+            entries[7],
+            AuditEntry {
+                kind: EntryKind::Group,
+                first_line: 4,
+                items: vec!['Z']
+            }
+        );
+
+        let frequencies = item_frequencies(&entries);
+        assert_eq!(frequencies[&priority('Z')], ('Z', 0, 1));
+        assert_eq!(frequencies[&priority('r')], ('r', 0, 1));
+        assert_eq!(frequencies[&priority('p')], ('p', 1, 0));
+    }
+
+    #[test]
+    fn test_audit_agrees_with_scores() {
+        let input = "abc\nabd\nxyz\nab\n";
+        let entries = audit(input, 1, 3).unwrap();
+
+        assert_eq!(
+            entries.last(),
+            Some(&AuditEntry {
+                kind: EntryKind::ShortGroup,
+                first_line: 4,
+                items: vec!['a', 'b']
+            })
+        );
+
+        let groups: Vec<AuditEntry> = entries
+            .into_iter()
+            .filter(|e| e.kind != EntryKind::Rucksack)
+            .collect();
+        assert_eq!(Tally::from_entries(groups), score_groups(input, 3).unwrap());
+        assert_eq!(score_groups(input, 3).unwrap().mismatches.len(), 2);
+    }
 
-    dbg!(total);
+    //only groups that score have a badge, so the table adds up to the same as the total.
+    #[test]
+    fn test_frequencies_agree_with_scores() {
+        let input = "abc\nade\nafg\nxyz\nxyv\nxyw\nq\n";
+        let groups = group_entries(input, 3).unwrap();
+        let frequencies = item_frequencies(&groups);
+
+        assert_eq!(frequencies[&priority('a')], ('a', 0, 1));
+        // the second group shares x and y, and the short group's q is not a badge either.
+        assert!(!frequencies.contains_key(&priority('x')));
+        assert!(!frequencies.contains_key(&priority('q')));
+
+        let badges: i32 = frequencies
+            .iter()
+            .map(|(priority, (_, _, badges))| priority * *badges as i32)
+            .sum();
+        assert_eq!(badges, score_groups(input, 3).unwrap().total);
+    }
+
+    #[test]
+    fn test_csv() {
+        let entries = vec![
+            AuditEntry {
+                kind: EntryKind::Rucksack,
+                first_line: 1,
+                items: vec!['a', 'B'],
+            },
+            AuditEntry {
+                kind: EntryKind::Group,
+                first_line: 1,
+                items: vec![],
+            },
+        ];
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &entries).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "kind,first_line,item,priority\nrucksack,1,a,1\nrucksack,1,B,28\ngroup,1,,\n"
+        );
+
+        let mut out = Vec::new();
+        write_frequency_csv(&mut out, &entries).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "item,priority,rucksacks,groups\na,1,1,0\nB,28,1,0\n"
+        );
+    }
 }